@@ -1,10 +1,11 @@
 // ref https://github.com/BitHotelOrg/bithotel-casper-contracts/blob/main/marketplace/contract/src/enums.rs
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
+use core::fmt;
 use casper_types::{
     account::AccountHash,
-    bytesrepr::{self, FromBytes, ToBytes},
-    CLType, CLTyped, ContractHash, ContractPackageHash, Key,
+    bytesrepr::{self, FromBytes, ToBytes, U8_SERIALIZED_LENGTH},
+    CLType, CLTyped, ContractHash, ContractPackageHash, Key, U256, U512,
 };
 
 /// An enum representing an [`AccountHash`] or a [`ContractPackageHash`].
@@ -94,4 +95,157 @@ impl FromBytes for Address {
 
         Ok((address, remainder))
     }
-}
\ No newline at end of file
+}
+const TOKEN_IDENTIFIER_INDEX_TAG: u8 = 0;
+const TOKEN_IDENTIFIER_HASH_TAG: u8 = 1;
+
+/// Identifies a token the way CEP-78 does, by ordinal index or by hash depending on the
+/// collection's identifier mode.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum TokenIdentifier {
+    /// Token id of a collection in ordinal mode.
+    Index(u64),
+    /// Token hash of a collection in hash mode.
+    Hash(String),
+}
+
+impl fmt::Display for TokenIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenIdentifier::Index(index) => write!(f, "{}", index),
+            TokenIdentifier::Hash(hash) => write!(f, "{}", hash),
+        }
+    }
+}
+
+impl CLTyped for TokenIdentifier {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+impl ToBytes for TokenIdentifier {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut result = bytesrepr::allocate_buffer(self)?;
+        match self {
+            TokenIdentifier::Index(index) => {
+                result.push(TOKEN_IDENTIFIER_INDEX_TAG);
+                result.append(&mut index.to_bytes()?);
+            }
+            TokenIdentifier::Hash(hash) => {
+                result.push(TOKEN_IDENTIFIER_HASH_TAG);
+                result.append(&mut hash.to_bytes()?);
+            }
+        }
+        Ok(result)
+    }
+
+    fn serialized_length(&self) -> usize {
+        U8_SERIALIZED_LENGTH +
+            (match self {
+                TokenIdentifier::Index(index) => index.serialized_length(),
+                TokenIdentifier::Hash(hash) => hash.serialized_length(),
+            })
+    }
+}
+
+impl FromBytes for TokenIdentifier {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (tag, remainder) = u8::from_bytes(bytes)?;
+        match tag {
+            TOKEN_IDENTIFIER_INDEX_TAG => {
+                let (index, remainder) = u64::from_bytes(remainder)?;
+                Ok((TokenIdentifier::Index(index), remainder))
+            }
+            TOKEN_IDENTIFIER_HASH_TAG => {
+                let (hash, remainder) = String::from_bytes(remainder)?;
+                Ok((TokenIdentifier::Hash(hash), remainder))
+            }
+            _ => Err(bytesrepr::Error::Formatting),
+        }
+    }
+}
+
+const PRIZE_NFT_TAG: u8 = 0;
+const PRIZE_NATIVE_TAG: u8 = 1;
+const PRIZE_CEP18_TAG: u8 = 2;
+
+/// What the winner of an item receives on claim.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Prize {
+    /// A token held by the contract on behalf of an allowed collection.
+    Nft {
+        collection: Key,
+        token_id: TokenIdentifier,
+    },
+    /// A fixed amount of motes escrowed in the prize purse.
+    Native(U512),
+    /// A fixed amount of a CEP-18 token held by the contract.
+    Cep18 {
+        token: Key,
+        amount: U256,
+    },
+}
+
+impl CLTyped for Prize {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+impl ToBytes for Prize {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut result = bytesrepr::allocate_buffer(self)?;
+        match self {
+            Prize::Nft { collection, token_id } => {
+                result.push(PRIZE_NFT_TAG);
+                result.append(&mut collection.to_bytes()?);
+                result.append(&mut token_id.to_bytes()?);
+            }
+            Prize::Native(amount) => {
+                result.push(PRIZE_NATIVE_TAG);
+                result.append(&mut amount.to_bytes()?);
+            }
+            Prize::Cep18 { token, amount } => {
+                result.push(PRIZE_CEP18_TAG);
+                result.append(&mut token.to_bytes()?);
+                result.append(&mut amount.to_bytes()?);
+            }
+        }
+        Ok(result)
+    }
+
+    fn serialized_length(&self) -> usize {
+        U8_SERIALIZED_LENGTH +
+            (match self {
+                Prize::Nft { collection, token_id } =>
+                    collection.serialized_length() + token_id.serialized_length(),
+                Prize::Native(amount) => amount.serialized_length(),
+                Prize::Cep18 { token, amount } =>
+                    token.serialized_length() + amount.serialized_length(),
+            })
+    }
+}
+
+impl FromBytes for Prize {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (tag, remainder) = u8::from_bytes(bytes)?;
+        match tag {
+            PRIZE_NFT_TAG => {
+                let (collection, remainder) = Key::from_bytes(remainder)?;
+                let (token_id, remainder) = TokenIdentifier::from_bytes(remainder)?;
+                Ok((Prize::Nft { collection, token_id }, remainder))
+            }
+            PRIZE_NATIVE_TAG => {
+                let (amount, remainder) = U512::from_bytes(remainder)?;
+                Ok((Prize::Native(amount), remainder))
+            }
+            PRIZE_CEP18_TAG => {
+                let (token, remainder) = Key::from_bytes(remainder)?;
+                let (amount, remainder) = U256::from_bytes(remainder)?;
+                Ok((Prize::Cep18 { token, amount }, remainder))
+            }
+            _ => Err(bytesrepr::Error::Formatting),
+        }
+    }
+}
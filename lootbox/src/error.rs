@@ -11,6 +11,46 @@ pub enum Error {
     MaxItemCount = 5,
     ItemNotFound = 6,
     RarityLevelNotFound = 7,
+    InsufficientPayment = 8,
+    CommitmentPending = 9,
+    CommitmentNotFound = 10,
+    CommitmentExpired = 11,
+    CommitmentNotExpired = 12,
+    RevealTooEarly = 13,
+    InvalidSecret = 14,
+    InvalidRarityWeights = 15,
+    AlreadyClaimed = 16,
+    NotItemOwner = 17,
+    InvalidItemBatch = 18,
+    InsufficientStock = 19,
+    NotAllowlisted = 20,
+    PurchaseLimitExceeded = 21,
+    SaleNotStarted = 22,
+    SaleEnded = 23,
+    Paused = 24,
+    InvalidSaleWindow = 25,
+    NotPendingOwner = 26,
+    InvalidRole = 27,
+    InsufficientBalance = 28,
+    TransferFailed = 29,
+    InvalidTarget = 30,
+    InvalidShares = 31,
+    // 32 was `PayeesLocked`, kept free so it is not mistaken for another error
+    NotPayee = 33,
+    NothingToRelease = 34,
+    InvalidIdentifierMode = 35,
+    InvalidCollection = 36,
+    CollectionNotAllowed = 37,
+    InvalidPrizeAmount = 38,
+    SealedBoxesDisabled = 39,
+    NotBoxOwner = 40,
+    ItemAssigned = 41,
+    SaleNotEnded = 42,
+    ItemsReserved = 43,
+    InvalidQuantity = 44,
+    InvalidRevealDelay = 45,
+    InvalidToken = 46,
+    AmountOverflow = 47,
 }
 
 impl From<Error> for ApiError {
@@ -18,3 +58,26 @@ impl From<Error> for ApiError {
         ApiError::User(error as u16)
     }
 }
+
+impl From<lootbox_core::Error> for Error {
+    fn from(error: lootbox_core::Error) -> Self {
+        match error {
+            lootbox_core::Error::ItemNotFound => Error::ItemNotFound,
+            lootbox_core::Error::ClaimNotFound => Error::ClaimNotFound,
+            lootbox_core::Error::NotItemOwner => Error::NotItemOwner,
+            lootbox_core::Error::AlreadyClaimed => Error::AlreadyClaimed,
+            lootbox_core::Error::InsufficientStock => Error::InsufficientStock,
+            lootbox_core::Error::ItemsReserved => Error::ItemsReserved,
+            lootbox_core::Error::PurchaseLimitExceeded => Error::PurchaseLimitExceeded,
+            lootbox_core::Error::LootboxLimit => Error::LootboxLimit,
+            lootbox_core::Error::InvalidQuantity => Error::InvalidQuantity,
+            lootbox_core::Error::InsufficientPayment => Error::InsufficientPayment,
+            lootbox_core::Error::NotAllowlisted => Error::NotAllowlisted,
+            lootbox_core::Error::SaleNotStarted => Error::SaleNotStarted,
+            lootbox_core::Error::SaleEnded => Error::SaleEnded,
+            lootbox_core::Error::Paused => Error::Paused,
+            lootbox_core::Error::InvalidSaleWindow => Error::InvalidSaleWindow,
+            lootbox_core::Error::InvalidRarityWeights => Error::InvalidRarityWeights,
+        }
+    }
+}
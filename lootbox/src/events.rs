@@ -1,33 +1,419 @@
-use crate::{ alloc::string::{ ToString }, utils::get_current_address };
-use alloc::{ collections::BTreeMap, vec::Vec };
-use casper_contract::contract_api::storage;
-use casper_types::{ URef, account::AccountHash };
+// Casper Event Standard (CES)
+// ref => https://github.com/make-software/casper-event-standard
+use alloc::{ collections::BTreeMap, string::{ String, ToString }, vec::Vec, vec };
+use casper_contract::{ contract_api::{ runtime, storage }, unwrap_or_revert::UnwrapOrRevert };
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{ self, Bytes, ToBytes },
+    CLType,
+    CLTyped,
+    Key,
+    U256,
+    U512,
+};
+
+use crate::utils;
+
+const EVENTS_DICT: &str = "__events";
+const EVENTS_LENGTH: &str = "__events_length";
+const EVENTS_SCHEMA: &str = "__events_schema";
+const EVENTS_CES_VERSION: &str = "__events_ces_version";
+const CES_VERSION: &str = "0.1.0";
+const EVENT_PREFIX: &str = "event_";
 
 pub enum LootboxEvent {
+    AddItem {
+        item_id: u64,
+        token_id: String,
+        rarity: u64,
+        name: String,
+        collection: Key,
+    },
+    SetRarity {
+        item_id: u64,
+        rarity: u64,
+    },
     Purchase {
-        caller: AccountHash,
-        lootbox_count: u64,
-        item_count: u64,
+        buyer: Key,
+        lootbox_id: u64,
+        price: U512,
+    },
+    Reveal {
+        buyer: Key,
+        item_ids: Vec<u64>,
+    },
+    CancelPurchase {
+        buyer: Key,
+        forfeited: U512,
+    },
+    Claim {
+        owner: Key,
+        item_id: u64,
+        token_id: Option<String>,
+    },
+    Withdraw {
+        recipient: Key,
+        amount: U512,
+    },
+    SetRarityWeights {
+        rarity_weights: Vec<u64>,
+    },
+    SetPurchaseLimit {
+        max_purchases_per_account: u64,
+    },
+    SetAllowlistOnly {
+        allowlist_only: bool,
+    },
+    UpdateAllowlist {
+        accounts: Vec<AccountHash>,
+        allowed: bool,
+    },
+    SetSaleWindow {
+        sale_start: Option<u64>,
+        sale_end: Option<u64>,
+    },
+    Pause,
+    Unpause,
+    OwnershipTransferStarted {
+        previous_owner: Key,
+        new_owner: Key,
+    },
+    OwnershipTransferred {
+        previous_owner: Key,
+        new_owner: Key,
+    },
+    RoleGranted {
+        account: Key,
+        role: u8,
+    },
+    RoleRevoked {
+        account: Key,
+        role: u8,
+    },
+    SetPayees {
+        payees: Vec<AccountHash>,
+        shares: Vec<u64>,
+    },
+    Release {
+        payee: Key,
+        amount: U512,
+    },
+    AddCollection {
+        collection: Key,
+        identifier_mode: u8,
+    },
+    RemoveCollection {
+        collection: Key,
+    },
+    AddNativePrize {
+        item_id: u64,
+        rarity: u64,
+        name: String,
+        amount: U512,
+    },
+    AddTokenPrize {
+        item_id: u64,
+        rarity: u64,
+        name: String,
+        token: Key,
+        amount: U256,
+    },
+    TransferBox {
+        box_id: u64,
+        from: Key,
+        to: Key,
+    },
+    OpenBox {
+        owner: Key,
+        box_id: u64,
+    },
+    RemoveItem {
+        item_id: u64,
+        recipient: Key,
     },
 }
 
-pub fn emit(event: &LootboxEvent) {
-    let mut events = Vec::new();
-    let mut param = BTreeMap::new();
-    param.insert(
-        "contract_package_hash",
-        get_current_address().as_contract_package_hash().unwrap().to_string()
-    );
-    match event {
-        LootboxEvent::Purchase { caller, lootbox_count, item_count } => {
-            param.insert("event_type", "purchase".to_string());
-            param.insert("caller", caller.to_string());
-            param.insert("lootbox_count", lootbox_count.to_string());
-            param.insert("item_count", item_count.to_string());
+impl LootboxEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            LootboxEvent::AddItem { .. } => "AddItem",
+            LootboxEvent::SetRarity { .. } => "SetRarity",
+            LootboxEvent::Purchase { .. } => "Purchase",
+            LootboxEvent::Reveal { .. } => "Reveal",
+            LootboxEvent::CancelPurchase { .. } => "CancelPurchase",
+            LootboxEvent::Claim { .. } => "Claim",
+            LootboxEvent::Withdraw { .. } => "Withdraw",
+            LootboxEvent::SetRarityWeights { .. } => "SetRarityWeights",
+            LootboxEvent::SetPurchaseLimit { .. } => "SetPurchaseLimit",
+            LootboxEvent::SetAllowlistOnly { .. } => "SetAllowlistOnly",
+            LootboxEvent::UpdateAllowlist { .. } => "UpdateAllowlist",
+            LootboxEvent::SetSaleWindow { .. } => "SetSaleWindow",
+            LootboxEvent::Pause => "Pause",
+            LootboxEvent::Unpause => "Unpause",
+            LootboxEvent::OwnershipTransferStarted { .. } => "OwnershipTransferStarted",
+            LootboxEvent::OwnershipTransferred { .. } => "OwnershipTransferred",
+            LootboxEvent::RoleGranted { .. } => "RoleGranted",
+            LootboxEvent::RoleRevoked { .. } => "RoleRevoked",
+            LootboxEvent::SetPayees { .. } => "SetPayees",
+            LootboxEvent::Release { .. } => "Release",
+            LootboxEvent::AddCollection { .. } => "AddCollection",
+            LootboxEvent::RemoveCollection { .. } => "RemoveCollection",
+            LootboxEvent::AddNativePrize { .. } => "AddNativePrize",
+            LootboxEvent::AddTokenPrize { .. } => "AddTokenPrize",
+            LootboxEvent::TransferBox { .. } => "TransferBox",
+            LootboxEvent::OpenBox { .. } => "OpenBox",
+            LootboxEvent::RemoveItem { .. } => "RemoveItem",
+        }
+    }
+
+    /// Serializes the event as its prefixed name followed by its fields, in schema order.
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut bytes = (EVENT_PREFIX.to_string() + self.name()).to_bytes()?;
+        match self {
+            LootboxEvent::AddItem { item_id, token_id, rarity, name, collection } => {
+                bytes.extend(item_id.to_bytes()?);
+                bytes.extend(token_id.to_bytes()?);
+                bytes.extend(rarity.to_bytes()?);
+                bytes.extend(name.to_bytes()?);
+                bytes.extend(collection.to_bytes()?);
+            }
+            LootboxEvent::SetRarity { item_id, rarity } => {
+                bytes.extend(item_id.to_bytes()?);
+                bytes.extend(rarity.to_bytes()?);
+            }
+            LootboxEvent::Purchase { buyer, lootbox_id, price } => {
+                bytes.extend(buyer.to_bytes()?);
+                bytes.extend(lootbox_id.to_bytes()?);
+                bytes.extend(price.to_bytes()?);
+            }
+            LootboxEvent::Reveal { buyer, item_ids } => {
+                bytes.extend(buyer.to_bytes()?);
+                bytes.extend(item_ids.to_bytes()?);
+            }
+            LootboxEvent::CancelPurchase { buyer, forfeited } => {
+                bytes.extend(buyer.to_bytes()?);
+                bytes.extend(forfeited.to_bytes()?);
+            }
+            LootboxEvent::Claim { owner, item_id, token_id } => {
+                bytes.extend(owner.to_bytes()?);
+                bytes.extend(item_id.to_bytes()?);
+                bytes.extend(token_id.to_bytes()?);
+            }
+            LootboxEvent::Withdraw { recipient, amount } => {
+                bytes.extend(recipient.to_bytes()?);
+                bytes.extend(amount.to_bytes()?);
+            }
+            LootboxEvent::SetRarityWeights { rarity_weights } => {
+                bytes.extend(rarity_weights.to_bytes()?);
+            }
+            LootboxEvent::SetPurchaseLimit { max_purchases_per_account } => {
+                bytes.extend(max_purchases_per_account.to_bytes()?);
+            }
+            LootboxEvent::SetAllowlistOnly { allowlist_only } => {
+                bytes.extend(allowlist_only.to_bytes()?);
+            }
+            LootboxEvent::UpdateAllowlist { accounts, allowed } => {
+                bytes.extend(accounts.to_bytes()?);
+                bytes.extend(allowed.to_bytes()?);
+            }
+            LootboxEvent::SetSaleWindow { sale_start, sale_end } => {
+                bytes.extend(sale_start.to_bytes()?);
+                bytes.extend(sale_end.to_bytes()?);
+            }
+            LootboxEvent::Pause | LootboxEvent::Unpause => {}
+            LootboxEvent::OwnershipTransferStarted { previous_owner, new_owner } |
+            LootboxEvent::OwnershipTransferred { previous_owner, new_owner } => {
+                bytes.extend(previous_owner.to_bytes()?);
+                bytes.extend(new_owner.to_bytes()?);
+            }
+            LootboxEvent::RoleGranted { account, role } |
+            LootboxEvent::RoleRevoked { account, role } => {
+                bytes.extend(account.to_bytes()?);
+                bytes.extend(role.to_bytes()?);
+            }
+            LootboxEvent::SetPayees { payees, shares } => {
+                bytes.extend(payees.to_bytes()?);
+                bytes.extend(shares.to_bytes()?);
+            }
+            LootboxEvent::Release { payee, amount } => {
+                bytes.extend(payee.to_bytes()?);
+                bytes.extend(amount.to_bytes()?);
+            }
+            LootboxEvent::AddCollection { collection, identifier_mode } => {
+                bytes.extend(collection.to_bytes()?);
+                bytes.extend(identifier_mode.to_bytes()?);
+            }
+            LootboxEvent::RemoveCollection { collection } => {
+                bytes.extend(collection.to_bytes()?);
+            }
+            LootboxEvent::AddNativePrize { item_id, rarity, name, amount } => {
+                bytes.extend(item_id.to_bytes()?);
+                bytes.extend(rarity.to_bytes()?);
+                bytes.extend(name.to_bytes()?);
+                bytes.extend(amount.to_bytes()?);
+            }
+            LootboxEvent::AddTokenPrize { item_id, rarity, name, token, amount } => {
+                bytes.extend(item_id.to_bytes()?);
+                bytes.extend(rarity.to_bytes()?);
+                bytes.extend(name.to_bytes()?);
+                bytes.extend(token.to_bytes()?);
+                bytes.extend(amount.to_bytes()?);
+            }
+            LootboxEvent::TransferBox { box_id, from, to } => {
+                bytes.extend(box_id.to_bytes()?);
+                bytes.extend(from.to_bytes()?);
+                bytes.extend(to.to_bytes()?);
+            }
+            LootboxEvent::OpenBox { owner, box_id } => {
+                bytes.extend(owner.to_bytes()?);
+                bytes.extend(box_id.to_bytes()?);
+            }
+            LootboxEvent::RemoveItem { item_id, recipient } => {
+                bytes.extend(item_id.to_bytes()?);
+                bytes.extend(recipient.to_bytes()?);
+            }
         }
+        Ok(bytes)
+    }
+}
+
+/// Field names and types of every event, keyed by event name.
+pub struct Schemas(BTreeMap<String, Vec<(String, CLType)>>);
+
+impl CLTyped for Schemas {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+impl ToBytes for Schemas {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        self.0.to_bytes()
     }
-    events.push(param);
-    for param in events {
-        let _: URef = storage::new_uref(param);
+
+    fn serialized_length(&self) -> usize {
+        self.0.serialized_length()
     }
 }
+
+fn field<T: CLTyped>(name: &str) -> (String, CLType) {
+    (name.to_string(), T::cl_type())
+}
+
+fn schemas() -> Schemas {
+    let mut schemas = BTreeMap::new();
+    schemas.insert("AddItem".to_string(), vec![
+        field::<u64>("item_id"),
+        field::<String>("token_id"),
+        field::<u64>("rarity"),
+        field::<String>("name"),
+        field::<Key>("collection")
+    ]);
+    schemas.insert("SetRarity".to_string(), vec![
+        field::<u64>("item_id"),
+        field::<u64>("rarity")
+    ]);
+    schemas.insert("Purchase".to_string(), vec![
+        field::<Key>("buyer"),
+        field::<u64>("lootbox_id"),
+        field::<U512>("price")
+    ]);
+    schemas.insert("Reveal".to_string(), vec![
+        field::<Key>("buyer"),
+        field::<Vec<u64>>("item_ids")
+    ]);
+    schemas.insert("CancelPurchase".to_string(), vec![
+        field::<Key>("buyer"),
+        field::<U512>("forfeited")
+    ]);
+    schemas.insert("Claim".to_string(), vec![
+        field::<Key>("owner"),
+        field::<u64>("item_id"),
+        field::<Option<String>>("token_id")
+    ]);
+    schemas.insert("Withdraw".to_string(), vec![
+        field::<Key>("recipient"),
+        field::<U512>("amount")
+    ]);
+    schemas.insert("SetRarityWeights".to_string(), vec![field::<Vec<u64>>("rarity_weights")]);
+    schemas.insert("SetPurchaseLimit".to_string(), vec![
+        field::<u64>("max_purchases_per_account")
+    ]);
+    schemas.insert("SetAllowlistOnly".to_string(), vec![field::<bool>("allowlist_only")]);
+    schemas.insert("UpdateAllowlist".to_string(), vec![
+        field::<Vec<AccountHash>>("accounts"),
+        field::<bool>("allowed")
+    ]);
+    schemas.insert("SetSaleWindow".to_string(), vec![
+        field::<Option<u64>>("sale_start"),
+        field::<Option<u64>>("sale_end")
+    ]);
+    schemas.insert("Pause".to_string(), vec![]);
+    schemas.insert("Unpause".to_string(), vec![]);
+    schemas.insert("OwnershipTransferStarted".to_string(), vec![
+        field::<Key>("previous_owner"),
+        field::<Key>("new_owner")
+    ]);
+    schemas.insert("OwnershipTransferred".to_string(), vec![
+        field::<Key>("previous_owner"),
+        field::<Key>("new_owner")
+    ]);
+    schemas.insert("RoleGranted".to_string(), vec![field::<Key>("account"), field::<u8>("role")]);
+    schemas.insert("RoleRevoked".to_string(), vec![field::<Key>("account"), field::<u8>("role")]);
+    schemas.insert("SetPayees".to_string(), vec![
+        field::<Vec<AccountHash>>("payees"),
+        field::<Vec<u64>>("shares")
+    ]);
+    schemas.insert("Release".to_string(), vec![field::<Key>("payee"), field::<U512>("amount")]);
+    schemas.insert("AddCollection".to_string(), vec![
+        field::<Key>("collection"),
+        field::<u8>("identifier_mode")
+    ]);
+    schemas.insert("RemoveCollection".to_string(), vec![field::<Key>("collection")]);
+    schemas.insert("AddNativePrize".to_string(), vec![
+        field::<u64>("item_id"),
+        field::<u64>("rarity"),
+        field::<String>("name"),
+        field::<U512>("amount")
+    ]);
+    schemas.insert("AddTokenPrize".to_string(), vec![
+        field::<u64>("item_id"),
+        field::<u64>("rarity"),
+        field::<String>("name"),
+        field::<Key>("token"),
+        field::<U256>("amount")
+    ]);
+    schemas.insert("TransferBox".to_string(), vec![
+        field::<u64>("box_id"),
+        field::<Key>("from"),
+        field::<Key>("to")
+    ]);
+    schemas.insert("OpenBox".to_string(), vec![field::<Key>("owner"), field::<u64>("box_id")]);
+    schemas.insert("RemoveItem".to_string(), vec![
+        field::<u64>("item_id"),
+        field::<Key>("recipient")
+    ]);
+    Schemas(schemas)
+}
+
+/// Creates the named keys indexers look for; must run in the contract context.
+pub fn init_events() {
+    storage::new_dictionary(EVENTS_DICT).unwrap_or_revert();
+    runtime::put_key(EVENTS_LENGTH, storage::new_uref(0u32).into());
+    runtime::put_key(EVENTS_SCHEMA, storage::new_uref(schemas()).into());
+    runtime::put_key(EVENTS_CES_VERSION, storage::new_uref(CES_VERSION.to_string()).into());
+}
+
+pub fn emit(event: &LootboxEvent) {
+    let events_length_uref = utils::get_uref(EVENTS_LENGTH);
+    let events_length: u32 = storage
+        ::read(events_length_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert();
+
+    let events_uref = utils::get_uref(EVENTS_DICT);
+    let event_bytes: Bytes = event.to_bytes().unwrap_or_revert().into();
+    storage::dictionary_put(events_uref, &events_length.to_string(), event_bytes);
+
+    storage::write(events_length_uref, events_length + 1);
+}
@@ -7,3 +7,4 @@ mod utils;
 mod lootbox;
 mod enums;
 mod events;
+mod store;
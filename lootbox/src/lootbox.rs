@@ -1,12 +1,15 @@
 use core::ops::Add;
 
-use alloc::{ string::{ String, ToString }, vec::Vec, vec };
+use alloc::{ boxed::Box, string::{ String, ToString }, vec::Vec, vec };
 
 use crate::{
+    enums::{ Prize, TokenIdentifier },
     error::Error,
-    utils::{ get_key, get_current_address, self },
-    events::{ emit, LootboxEvent },
+    utils::{ get_key, self },
+    events::{ emit, init_events, LootboxEvent },
+    store::CasperStore,
 };
+use lootbox_core::{ draw::{ self, sha3_256 }, ledger, pool, sale, Store };
 
 use casper_types::{
     account::AccountHash,
@@ -19,6 +22,7 @@ use casper_types::{
     EntryPointType,
     EntryPoints,
     contracts::NamedKeys,
+    U256,
     U512,
     RuntimeArgs,
     runtime_args,
@@ -29,7 +33,6 @@ use casper_types_derive::{ CLTyped, FromBytes, ToBytes };
 
 use casper_contract::contract_api::{ runtime, storage, system };
 use casper_contract::unwrap_or_revert::UnwrapOrRevert;
-use tiny_keccak::{ Sha3, Hasher };
 
 const OWNER: &str = "owner";
 const NAME: &str = "name";
@@ -42,144 +45,584 @@ const LOOTBOX_COUNT: &str = "lootbox_count";
 const MAX_ITEMS: &str = "max_items";
 const ITEM_COUNT: &str = "item_count";
 const ITEMS: &str = "items";
-const ITEM_OWNERS: &str = "item_owners";
+pub(crate) const ITEM_OWNERS: &str = "item_owners";
 const NFT_COLLECTION: &str = "nft_collection";
 const TOKEN_ID: &str = "token_id";
 const ITEM_NAME: &str = "item_name";
+const TOKEN_IDS: &str = "token_ids";
+const TOKEN_HASH: &str = "token_hash";
+const TOKEN_HASHES: &str = "token_hashes";
+const IDENTIFIER_MODE: &str = "identifier_mode";
+const COLLECTION: &str = "collection";
+const COLLECTIONS: &str = "collections";
+const ITEM_NAMES: &str = "item_names";
+const RARITIES: &str = "rarities";
 const DEPOSITED_ITEM_COUNT: &str = "deposited_item_count";
 const ITEM_INDEX: &str = "item_index";
 const PURSE: &str = "purse";
+const PAYMENT_PURSE: &str = "payment_purse";
+const PRIZE_PURSE: &str = "prize_purse";
+const SOURCE_PURSE: &str = "source_purse";
+const TOKEN: &str = "token";
+const COMMITMENTS: &str = "commitments";
+const COMMITMENT: &str = "commitment";
+const SECRET: &str = "secret";
+const REVEAL_WINDOW: &str = "reveal_window";
+const REVEAL_DELAY: &str = "reveal_delay";
+const ENTROPY: &str = "entropy";
+const ENTROPY_ROUNDS: &str = "entropy_rounds";
+const ENTROPY_ROUND_COUNT: &str = "entropy_round_count";
+const ESCROWED_AMOUNT: &str = "escrowed_amount";
+const RARITY_WEIGHTS: &str = "rarity_weights";
+pub(crate) const ITEM_POOL: &str = "item_pool";
+pub(crate) const ITEM_POOL_SIZES: &str = "item_pool_sizes";
+pub(crate) const ITEM_POSITIONS: &str = "item_positions";
+pub(crate) const CLAIMED_ITEMS: &str = "claimed_items";
+const REMOVED_ITEMS: &str = "removed_items";
+pub(crate) const PENDING_CLAIMS: &str = "pending_claims";
+pub(crate) const RESERVED_ITEM_COUNT: &str = "reserved_item_count";
+const MAX_PURCHASES_PER_ACCOUNT: &str = "max_purchases_per_account";
+pub(crate) const ACCOUNT_PURCHASES: &str = "account_purchases";
+const ACCOUNT_PAYMENTS: &str = "account_payments";
+pub(crate) const ALLOWLIST_ONLY: &str = "allowlist_only";
+pub(crate) const ALLOWLIST: &str = "allowlist";
+const ACCOUNTS: &str = "accounts";
+pub(crate) const SALE_START: &str = "sale_start";
+pub(crate) const SALE_END: &str = "sale_end";
+pub(crate) const PAUSED: &str = "paused";
+const PENDING_OWNER: &str = "pending_owner";
+const NEW_OWNER: &str = "new_owner";
+const ROLES: &str = "roles";
+const ROLE: &str = "role";
+const ACCOUNT: &str = "account";
+const AMOUNT: &str = "amount";
+const TARGET: &str = "target";
+const PAYEES: &str = "payees";
+const SHARES: &str = "shares";
+const PAYEE_SHARES: &str = "payee_shares";
+const RELEASED: &str = "released";
+const EARNED: &str = "earned";
+const OWNER_EARNED: &str = "owner_earned";
+const SETTLED_REVENUE: &str = "settled_revenue";
+const TOTAL_RELEASED: &str = "total_released";
+const TOTAL_REVENUE: &str = "total_revenue";
+const TOTAL_WITHDRAWN: &str = "total_withdrawn";
+const PAYMENT_TOKEN: &str = "payment_token";
+const SEALED_BOXES: &str = "sealed_boxes";
+const BOX_OWNERS: &str = "box_owners";
+const BOX_ID: &str = "box_id";
+const RECIPIENT: &str = "recipient";
+const LIMIT: &str = "limit";
+const QUANTITY: &str = "quantity";
 const RARITY: &str = "rarity";
 const STORAGE_KEY: &str = "storage_key";
 
+// rarity levels run from 0 to RARITY_LEVELS - 1
+const RARITY_LEVELS: u64 = 3;
+
+// token identifier modes, matching CEP-78's NFTIdentifierMode
+const IDENTIFIER_MODE_ORDINAL: u8 = 0;
+const IDENTIFIER_MODE_HASH: u8 = 1;
+
+// payee shares are expressed in basis points of the revenue
+const BASIS_POINTS: u64 = 10_000;
+
+// roles, stored as bit flags per account
+const ROLE_INVENTORY_MANAGER: u8 = 1;
+const ROLE_TREASURER: u8 = 2;
+
+// commitment status
+const COMMITMENT_PENDING: u8 = 0;
+const COMMITMENT_REVEALED: u8 = 1;
+const COMMITMENT_CANCELLED: u8 = 2;
+
 //entry points
 const ENTRY_POINT_ADD_ITEM: &str = "add_item";
+const ENTRY_POINT_ADD_ITEMS: &str = "add_items";
 const ENTRY_POINT_INIT: &str = "init";
 const ENTRY_POINT_PURCHASE: &str = "purchase";
 const ENTRY_POINT_CLAIM: &str = "claim";
 const ENTRY_POINT_GET_PRICE: &str = "get_price";
-const ENTRY_POINT_GET_PURSE: &str = "get_purse";
+const ENTRY_POINT_GET_PAYMENT_TOKEN: &str = "get_payment_token";
 const ENTRY_POINT_SET_RARITY: &str = "set_rarity";
 const ENTRY_POINT_WITHDRAW: &str = "withdraw";
+const ENTRY_POINT_REVEAL: &str = "reveal";
+const ENTRY_POINT_CANCEL_PURCHASE: &str = "cancel_purchase";
+const ENTRY_POINT_SET_RARITY_WEIGHTS: &str = "set_rarity_weights";
+const ENTRY_POINT_CLAIM_ALL: &str = "claim_all";
+const ENTRY_POINT_GET_REMAINING_STOCK: &str = "get_remaining_stock";
+const ENTRY_POINT_SET_PURCHASE_LIMIT: &str = "set_purchase_limit";
+const ENTRY_POINT_SET_ALLOWLIST_ONLY: &str = "set_allowlist_only";
+const ENTRY_POINT_ADD_TO_ALLOWLIST: &str = "add_to_allowlist";
+const ENTRY_POINT_REMOVE_FROM_ALLOWLIST: &str = "remove_from_allowlist";
+const ENTRY_POINT_SET_SALE_WINDOW: &str = "set_sale_window";
+const ENTRY_POINT_PAUSE: &str = "pause";
+const ENTRY_POINT_UNPAUSE: &str = "unpause";
+const ENTRY_POINT_TRANSFER_OWNERSHIP: &str = "transfer_ownership";
+const ENTRY_POINT_ACCEPT_OWNERSHIP: &str = "accept_ownership";
+const ENTRY_POINT_GRANT_ROLE: &str = "grant_role";
+const ENTRY_POINT_REVOKE_ROLE: &str = "revoke_role";
+const ENTRY_POINT_SET_PAYEES: &str = "set_payees";
+const ENTRY_POINT_RELEASE: &str = "release";
+const ENTRY_POINT_ADD_COLLECTION: &str = "add_collection";
+const ENTRY_POINT_REMOVE_COLLECTION: &str = "remove_collection";
+const ENTRY_POINT_ADD_NATIVE_PRIZE: &str = "add_native_prize";
+const ENTRY_POINT_ADD_TOKEN_PRIZE: &str = "add_token_prize";
+const ENTRY_POINT_TRANSFER_BOX: &str = "transfer_box";
+const ENTRY_POINT_OPEN_BOX: &str = "open_box";
+const ENTRY_POINT_REMOVE_ITEM: &str = "remove_item";
+const ENTRY_POINT_RECLAIM_UNSOLD: &str = "reclaim_unsold";
 
 #[derive(Clone, Debug, CLTyped, ToBytes, FromBytes)]
 pub struct Item {
     pub id: u64,
     pub rarity: u64,
-    pub token_id: u64,
     pub name: String,
+    pub prize: Prize,
+}
+
+/// The entropy accumulator as one state-changing call left it.
+#[derive(Clone, Debug, CLTyped, ToBytes, FromBytes)]
+pub struct EntropyRound {
+    pub time: u64,
+    pub entropy: [u8; 32],
 }
 
-// admin function
+/// A buyer's pending lootbox, opened later by revealing the secret behind `hash`.
+#[derive(Clone, Debug, CLTyped, ToBytes, FromBytes)]
+pub struct Commitment {
+    pub hash: [u8; 32],
+    pub committed_at: u64,
+    pub expires_at: u64,
+    pub amount: U512,
+    pub status: u8,
+    /// Number of boxes the commitment reveals.
+    pub quantity: u64,
+    /// The sealed box being opened, if the commitment came from `open_box`.
+    pub box_id: Option<u64>,
+}
+
+// inventory manager function
 #[no_mangle]
 pub extern "C" fn add_item() {
-    check_admin_account();
+    check_role(ROLE_INVENTORY_MANAGER);
 
-    let token_id: u64 = runtime::get_named_arg(TOKEN_ID);
+    let collection: Key = runtime::get_named_arg(COLLECTION);
+    let token_id: TokenIdentifier = match get_identifier_mode(collection) {
+        IDENTIFIER_MODE_HASH => TokenIdentifier::Hash(runtime::get_named_arg(TOKEN_HASH)),
+        _ => TokenIdentifier::Index(runtime::get_named_arg(TOKEN_ID)),
+    };
     let item_name: String = runtime::get_named_arg(ITEM_NAME);
     let rarity: u64 = runtime::get_named_arg(RARITY);
 
-    if rarity > 2 {
-        runtime::revert(Error::RarityLevelNotFound);
+    deposit_items(collection, vec![token_id], vec![item_name], vec![rarity]);
+}
+
+// inventory manager function
+#[no_mangle]
+pub extern "C" fn add_items() {
+    check_role(ROLE_INVENTORY_MANAGER);
+
+    let collection: Key = runtime::get_named_arg(COLLECTION);
+    let token_ids: Vec<TokenIdentifier> = match get_identifier_mode(collection) {
+        IDENTIFIER_MODE_HASH => {
+            let token_hashes: Vec<String> = runtime::get_named_arg(TOKEN_HASHES);
+            token_hashes.into_iter().map(TokenIdentifier::Hash).collect()
+        }
+        _ => {
+            let token_ids: Vec<u64> = runtime::get_named_arg(TOKEN_IDS);
+            token_ids.into_iter().map(TokenIdentifier::Index).collect()
+        }
+    };
+    let item_names: Vec<String> = runtime::get_named_arg(ITEM_NAMES);
+    let rarities: Vec<u64> = runtime::get_named_arg(RARITIES);
+
+    if token_ids.len() != item_names.len() || token_ids.len() != rarities.len() {
+        runtime::revert(Error::InvalidItemBatch);
     }
 
-    let contract_address = get_current_address();
-    let caller: AccountHash = runtime::get_caller();
-    let collection: Key = utils::read_from(NFT_COLLECTION);
-    let deposited_item_count: u64 = utils::read_from(DEPOSITED_ITEM_COUNT);
-    let max_items: u64 = utils::read_from(MAX_ITEMS);
+    deposit_items(collection, token_ids, item_names, rarities);
+}
 
-    if deposited_item_count >= max_items {
-        runtime::revert(Error::MaxItemCount);
+// inventory manager function
+/// Escrows `amount` motes from the caller's `source_purse` into the prize purse as a new item.
+/// Call it through the `lootbox_prize_deposit_contract` session, which funds a fresh purse,
+/// since passing a main purse would give this contract write access to it.
+#[no_mangle]
+pub extern "C" fn add_native_prize() {
+    check_role(ROLE_INVENTORY_MANAGER);
+
+    let source_purse: URef = runtime::get_named_arg(SOURCE_PURSE);
+    let amount: U512 = runtime::get_named_arg(AMOUNT);
+    let item_name: String = runtime::get_named_arg(ITEM_NAME);
+    let rarity: u64 = runtime::get_named_arg(RARITY);
+
+    if amount.is_zero() {
+        runtime::revert(Error::InvalidPrizeAmount);
     }
 
-    let collection_hash: ContractHash = collection.into_hash().map(ContractHash::new).unwrap();
+    let balance: U512 = system
+        ::get_purse_balance(source_purse)
+        .unwrap_or_revert_with(Error::InsufficientBalance);
 
-    let is_approved: bool = is_approved_for_all(
-        collection_hash,
-        caller.into(),
-        contract_address.into()
-    );
+    if balance < amount {
+        runtime::revert(Error::InsufficientBalance);
+    }
 
-    if !is_approved {
-        runtime::revert(Error::NotApproved);
+    let prize_purse: URef = utils::get_uref(PRIZE_PURSE);
+    system
+        ::transfer_from_purse_to_purse(source_purse, prize_purse, amount, None)
+        .unwrap_or_revert_with(Error::TransferFailed);
+
+    let item_id: u64 = store_prize(Prize::Native(amount), item_name.clone(), rarity);
+
+    emit(&(LootboxEvent::AddNativePrize { item_id, rarity, name: item_name, amount }));
+}
+
+// inventory manager function
+/// Moves `amount` of a CEP-18 token from the caller into the contract as a new item.
+/// The caller must have approved the contract package for at least `amount` beforehand.
+#[no_mangle]
+pub extern "C" fn add_token_prize() {
+    check_role(ROLE_INVENTORY_MANAGER);
+
+    let token: Key = runtime::get_named_arg(TOKEN);
+    let amount: U256 = runtime::get_named_arg(AMOUNT);
+    let item_name: String = runtime::get_named_arg(ITEM_NAME);
+    let rarity: u64 = runtime::get_named_arg(RARITY);
+
+    if amount.is_zero() {
+        runtime::revert(Error::InvalidPrizeAmount);
     }
 
-    // check owner is caller
-    transfer(collection_hash, caller.into(), contract_address.into(), token_id);
+    let token_hash: ContractHash = get_token_hash(token);
+    let caller: Key = Key::Account(runtime::get_caller());
+    let contract_key: Key = utils::get_current_package_hash().into();
 
-    let items_dict: URef = *runtime::get_key(ITEMS).unwrap().as_uref().unwrap();
+    if cep18_allowance(token_hash, caller, contract_key) < amount {
+        runtime::revert(Error::NotApproved);
+    }
 
-    storage::dictionary_put(items_dict, &deposited_item_count.to_string(), Item {
-        id: deposited_item_count.into(),
-        rarity,
-        token_id,
-        name: item_name,
-    });
+    cep18_transfer_from(token_hash, caller, contract_key, amount);
 
-    runtime::put_key(
-        DEPOSITED_ITEM_COUNT,
-        storage::new_uref(deposited_item_count.add(1u64)).into()
-    );
+    let item_id: u64 = store_prize(Prize::Cep18 { token, amount }, item_name.clone(), rarity);
+
+    emit(&(LootboxEvent::AddTokenPrize { item_id, rarity, name: item_name, token, amount }));
 }
 
 #[no_mangle]
 pub extern "C" fn purchase() {
+    check_sale_open();
+
+    let quantity: u64 = runtime::get_named_arg(QUANTITY);
     let lootbox_count: u64 = utils::read_from(LOOTBOX_COUNT);
     let max_lootboxes: u64 = utils::read_from(MAX_LOOTBOXES);
 
-    if lootbox_count >= max_lootboxes {
-        runtime::revert(Error::LootboxLimit);
+    sale
+        ::check_quantity(lootbox_count, max_lootboxes, quantity)
+        .map_err(Error::from)
+        .unwrap_or_revert();
+
+    let caller: AccountHash = runtime::get_caller();
+    let commitments: URef = utils::get_uref(COMMITMENTS);
+    let account_key = utils::to_dictionary_key(&caller.value());
+    let sealed_boxes: bool = utils::read_from(SEALED_BOXES);
+
+    if !sealed_boxes {
+        check_no_pending_commitment(commitments, &account_key);
     }
 
+    sale::check_allowlisted(&CasperStore, caller).map_err(Error::from).unwrap_or_revert();
+
+    // every sold box keeps its items set aside until it is revealed or cancelled
+    let max_purchases_per_account: u64 = utils::read_from(MAX_PURCHASES_PER_ACCOUNT);
     let items_per_lootbox: u64 = utils::read_from(ITEMS_PER_LOOTBOX);
-    let mut item_count: u64 = utils::read_from(ITEM_COUNT);
-    let max_items: u64 = utils::read_from(MAX_ITEMS);
 
-    let item_owners = *runtime::get_key(ITEM_OWNERS).unwrap().as_uref().unwrap();
+    ledger
+        ::reserve_purchase(
+            &mut CasperStore,
+            caller,
+            max_purchases_per_account,
+            quantity,
+            items_per_lootbox
+        )
+        .map_err(Error::from)
+        .unwrap_or_revert();
+
+    let lootbox_price: U512 = utils::read_from(LOOTBOX_PRICE);
+    let total_price: U512 = sale
+        ::total_price(lootbox_price, quantity)
+        .map_err(Error::from)
+        .unwrap_or_revert();
+    collect_payment(caller, total_price);
+
+    if sealed_boxes {
+        // a sealed box is paid for outright; only opening it goes through commit-reveal
+        let box_owners: URef = utils::get_uref(BOX_OWNERS);
+        for box_id in lootbox_count..lootbox_count + quantity {
+            storage::dictionary_put(box_owners, &box_id.to_string(), Some(caller));
+        }
+
+        let total_revenue: U512 = utils::read_from(TOTAL_REVENUE);
+        runtime::put_key(TOTAL_REVENUE, storage::new_uref(total_revenue + total_price).into());
+    } else {
+        let commitment_hash: [u8; 32] = runtime::get_named_arg(COMMITMENT);
+
+        // payment stays in escrow until the boxes are revealed or the commitment is forfeited
+        let escrowed_amount: U512 = utils::read_from(ESCROWED_AMOUNT);
+        runtime::put_key(
+            ESCROWED_AMOUNT,
+            storage::new_uref(escrowed_amount + total_price).into()
+        );
+
+        store_commitment(commitments, &account_key, commitment_hash, total_price, quantity, None);
+    }
+
+    let now: u64 = runtime::get_blocktime().into();
+    stir_entropy(&[&caller.value(), &lootbox_count.to_be_bytes(), &now.to_be_bytes()]);
+
+    runtime::put_key(LOOTBOX_COUNT, storage::new_uref(lootbox_count.add(quantity)).into());
+
+    for lootbox_id in lootbox_count..lootbox_count + quantity {
+        emit(
+            &(LootboxEvent::Purchase {
+                buyer: Key::Account(caller),
+                lootbox_id,
+                price: lootbox_price,
+            })
+        );
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reveal() {
+    let secret: String = runtime::get_named_arg(SECRET);
+
     let caller: AccountHash = runtime::get_caller();
+    let commitments: URef = utils::get_uref(COMMITMENTS);
+    let account_key = utils::to_dictionary_key(&caller.value());
 
-    for _i in 0..items_per_lootbox {
-        if item_count >= max_items {
-            break;
-        }
+    let commitment: Commitment = storage
+        ::dictionary_get::<Commitment>(commitments, &account_key)
+        .unwrap_or_revert()
+        .filter(|commitment| commitment.status == COMMITMENT_PENDING)
+        .unwrap_or_revert_with(Error::CommitmentNotFound);
+
+    // the reveal has to land in a later block, at least `reveal_delay` after the commitment
+    let now: u64 = runtime::get_blocktime().into();
+    let reveal_delay: u64 = utils::read_from(REVEAL_DELAY);
+    let reveal_from: u64 = commitment.committed_at.saturating_add(reveal_delay.max(1));
+
+    if now < reveal_from {
+        runtime::revert(Error::RevealTooEarly);
+    }
+
+    if now > commitment.expires_at {
+        runtime::revert(Error::CommitmentExpired);
+    }
+
+    if sha3_256(&[secret.as_bytes()]) != commitment.hash {
+        runtime::revert(Error::InvalidSecret);
+    }
+
+    // the seed uses the accumulator as it stood when the delay ran out, which calls made after
+    // that can no longer change, so picking the block to reveal in does not change the draw.
+    // Only calls made by others between the commitment and the end of the delay hide it from
+    // the buyer; if there are none, the buyer can already work it out when committing.
+    let entropy = entropy_before(reveal_from);
+    let seed = sha3_256(&[secret.as_bytes(), &entropy]);
+    stir_entropy(&[&commitment.hash, &now.to_be_bytes(), &caller.value()]);
+
+    let items_per_lootbox: u64 = utils::read_from(ITEMS_PER_LOOTBOX);
+    let rarity_weights: Vec<u64> = utils::read_from(RARITY_WEIGHTS);
+    let item_count: u64 = utils::read_from(ITEM_COUNT);
+    let item_total: u64 = commitment.quantity * items_per_lootbox;
+
+    let won_items: Vec<u64> = ledger
+        ::assign_items(&mut CasperStore, &seed, caller, &rarity_weights, item_total, item_count)
+        .map_err(Error::from)
+        .unwrap_or_revert();
+
+    // if the pools ran dry before every reserved item was drawn,
+    // the undelivered part of the payment goes back to the buyer
+    let shortfall: u64 = item_total - (won_items.len() as u64);
+    let mut refund: U512 = U512::zero();
+
+    if shortfall > 0 {
+        ledger::release_items(&mut CasperStore, shortfall);
+
+        refund = (commitment.amount * U512::from(shortfall)) / U512::from(item_total);
+        refund_payment(caller, refund);
+    }
 
-        let item_id = get_random_item_id(max_items, item_count);
+    runtime::put_key(
+        ITEM_COUNT,
+        storage::new_uref(item_count + (won_items.len() as u64)).into()
+    );
+
+    // once revealed, the payment can no longer be refunded and counts as revenue
+    let escrowed_amount: U512 = utils::read_from(ESCROWED_AMOUNT);
+    runtime::put_key(
+        ESCROWED_AMOUNT,
+        storage::new_uref(escrowed_amount - commitment.amount).into()
+    );
+
+    let total_revenue: U512 = utils::read_from(TOTAL_REVENUE);
+    runtime::put_key(
+        TOTAL_REVENUE,
+        storage::new_uref(total_revenue + commitment.amount - refund).into()
+    );
+
+    storage::dictionary_put(commitments, &account_key, Commitment {
+        status: COMMITMENT_REVEALED,
+        ..commitment
+    });
+
+    emit(&(LootboxEvent::Reveal { buyer: Key::Account(caller), item_ids: won_items }))
+}
+
+#[no_mangle]
+pub extern "C" fn cancel_purchase() {
+    let caller: AccountHash = runtime::get_caller();
+    let commitments: URef = utils::get_uref(COMMITMENTS);
+    let account_key = utils::to_dictionary_key(&caller.value());
 
-        storage::dictionary_put(item_owners, &item_id.to_string(), caller);
+    let commitment: Commitment = storage
+        ::dictionary_get::<Commitment>(commitments, &account_key)
+        .unwrap_or_revert()
+        .filter(|commitment| commitment.status == COMMITMENT_PENDING)
+        .unwrap_or_revert_with(Error::CommitmentNotFound);
 
-        item_count += 1;
+    let now: u64 = runtime::get_blocktime().into();
+
+    if now <= commitment.expires_at {
+        runtime::revert(Error::CommitmentNotExpired);
+    }
+
+    match commitment.box_id {
+        // handing the box back would let its holder abandon every opening they dislike and
+        // open it again, so the box stays burned. The holder may have been given the box,
+        // so the buyer's purchase count is left alone and only its items are released.
+        Some(_) => {
+            let items_per_lootbox: u64 = utils::read_from(ITEMS_PER_LOOTBOX);
+            ledger::release_items(&mut CasperStore, commitment.quantity * items_per_lootbox);
+        }
+        // refunding an unrevealed box would let a buyer walk away from every outcome they
+        // dislike, so the payment is forfeited and only the reserved items are released
+        None => {
+            let escrowed_amount: U512 = utils::read_from(ESCROWED_AMOUNT);
+            runtime::put_key(
+                ESCROWED_AMOUNT,
+                storage::new_uref(escrowed_amount - commitment.amount).into()
+            );
+
+            let total_revenue: U512 = utils::read_from(TOTAL_REVENUE);
+            runtime::put_key(
+                TOTAL_REVENUE,
+                storage::new_uref(total_revenue + commitment.amount).into()
+            );
+
+            let lootbox_count: u64 = utils::read_from(LOOTBOX_COUNT);
+            runtime::put_key(
+                LOOTBOX_COUNT,
+                storage::new_uref(lootbox_count - commitment.quantity).into()
+            );
+
+            let items_per_lootbox: u64 = utils::read_from(ITEMS_PER_LOOTBOX);
+            ledger::release_purchase(
+                &mut CasperStore,
+                caller,
+                commitment.quantity,
+                items_per_lootbox
+            );
+        }
     }
 
-    runtime::put_key(ITEM_COUNT, storage::new_uref(item_count).into());
-    runtime::put_key(LOOTBOX_COUNT, storage::new_uref(lootbox_count.add(1u64)).into());
+    storage::dictionary_put(commitments, &account_key, Commitment {
+        status: COMMITMENT_CANCELLED,
+        ..commitment
+    });
+
+    // a sealed box was paid for when it was bought, so its opening has nothing escrowed to lose
+    emit(
+        &(LootboxEvent::CancelPurchase {
+            buyer: Key::Account(caller),
+            forfeited: commitment.amount,
+        })
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn transfer_box() {
+    let box_id: u64 = runtime::get_named_arg(BOX_ID);
+    let recipient: AccountHash = runtime::get_named_arg(RECIPIENT);
+
+    let caller: AccountHash = runtime::get_caller();
+    check_box_owner(box_id, caller);
+
+    let box_owners: URef = utils::get_uref(BOX_OWNERS);
+    storage::dictionary_put(box_owners, &box_id.to_string(), Some(recipient));
+
+    emit(
+        &(LootboxEvent::TransferBox {
+            box_id,
+            from: Key::Account(caller),
+            to: Key::Account(recipient),
+        })
+    );
+}
+
+/// Burns a sealed box and commits to the secret that will draw its items on `reveal`.
+#[no_mangle]
+pub extern "C" fn open_box() {
+    let box_id: u64 = runtime::get_named_arg(BOX_ID);
+    let commitment_hash: [u8; 32] = runtime::get_named_arg(COMMITMENT);
+
+    let caller: AccountHash = runtime::get_caller();
+    check_box_owner(box_id, caller);
+
+    let commitments: URef = utils::get_uref(COMMITMENTS);
+    let account_key = utils::to_dictionary_key(&caller.value());
+    check_no_pending_commitment(commitments, &account_key);
+
+    let box_owners: URef = utils::get_uref(BOX_OWNERS);
+    storage::dictionary_put(box_owners, &box_id.to_string(), None::<AccountHash>);
+
+    // the box was paid for at purchase, so there is nothing left to escrow
+    store_commitment(commitments, &account_key, commitment_hash, U512::zero(), 1, Some(box_id));
 
-    emit(&&(LootboxEvent::Purchase { caller, lootbox_count, item_count }))
+    let now: u64 = runtime::get_blocktime().into();
+    stir_entropy(&[&caller.value(), &box_id.to_be_bytes(), &now.to_be_bytes()]);
+
+    emit(&(LootboxEvent::OpenBox { owner: Key::Account(caller), box_id }));
 }
 
 #[no_mangle]
 pub extern "C" fn claim() {
     let item_index: u64 = runtime::get_named_arg(ITEM_INDEX);
 
-    let item_owners = *runtime::get_key(ITEM_OWNERS).unwrap().as_uref().unwrap();
+    let caller: AccountHash = runtime::get_caller();
 
-    let to_account = storage
-        ::dictionary_get::<AccountHash>(item_owners, &item_index.to_string())
-        .unwrap()
-        .unwrap_or_revert_with(Error::ClaimNotFound);
+    ledger
+        ::take_claim(&mut CasperStore, caller, item_index)
+        .map_err(Error::from)
+        .unwrap_or_revert();
 
-    let items: URef = *runtime::get_key(ITEMS).unwrap().as_uref().unwrap();
-    let data: Item = storage
-        ::dictionary_get::<Item>(items, &item_index.to_string())
-        .unwrap()
-        .unwrap();
+    deliver_item(item_index, caller);
+}
 
-    let collection: Key = utils::read_from(NFT_COLLECTION);
-    let collection_hash: ContractHash = collection.into_hash().map(ContractHash::new).unwrap();
+#[no_mangle]
+pub extern "C" fn claim_all() {
+    let caller: AccountHash = runtime::get_caller();
 
-    let contract_address = get_current_address();
+    let unclaimed_items: Vec<u64> = ledger
+        ::take_all_claims(&mut CasperStore, caller)
+        .map_err(Error::from)
+        .unwrap_or_revert();
 
-    transfer(collection_hash, contract_address.into(), Key::Account(to_account), data.token_id)
+    for item_index in unclaimed_items {
+        deliver_item(item_index, caller);
+    }
 }
 
 #[no_mangle]
@@ -189,34 +632,68 @@ pub extern "C" fn get_price() {
     runtime::ret(CLValue::from_t(price).unwrap_or_revert());
 }
 
+/// The CEP-18 token the sale is priced in, or `None` for CSPR.
 #[no_mangle]
-pub extern "C" fn get_purse() {
-    let raffle_purse = match runtime::get_key(PURSE) {
-        Some(purse_key) => purse_key.into_uref().unwrap_or_revert(),
-        None => {
-            let new_purse = system::create_purse();
-            runtime::put_key(PURSE, new_purse.into());
-            new_purse
-        }
-    };
+pub extern "C" fn get_payment_token() {
+    let payment_token: Option<Key> = utils::read_from(PAYMENT_TOKEN);
 
-    runtime::ret(CLValue::from_t(raffle_purse.into_add()).unwrap_or_revert());
+    runtime::ret(CLValue::from_t(payment_token).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn get_remaining_stock() {
+    runtime::ret(CLValue::from_t(pool::remaining_stock(&CasperStore)).unwrap_or_revert());
 }
 
 #[no_mangle]
 pub extern "C" fn init() {
     check_admin_account();
+    let payees: Vec<AccountHash> = runtime::get_named_arg(PAYEES);
+    let shares: Vec<u64> = runtime::get_named_arg(SHARES);
+    let nft_collection: Key = runtime::get_named_arg(NFT_COLLECTION);
+    let identifier_mode: u8 = runtime::get_named_arg(IDENTIFIER_MODE);
+
     storage::new_dictionary(ITEM_OWNERS).unwrap_or_default();
     storage::new_dictionary(ITEMS).unwrap_or_default();
+    storage::new_dictionary(COMMITMENTS).unwrap_or_default();
+    storage::new_dictionary(ITEM_POOL).unwrap_or_default();
+    storage::new_dictionary(ITEM_POSITIONS).unwrap_or_default();
+    storage::new_dictionary(CLAIMED_ITEMS).unwrap_or_default();
+    storage::new_dictionary(PENDING_CLAIMS).unwrap_or_default();
+    storage::new_dictionary(ACCOUNT_PURCHASES).unwrap_or_default();
+    storage::new_dictionary(ACCOUNT_PAYMENTS).unwrap_or_default();
+    storage::new_dictionary(ALLOWLIST).unwrap_or_default();
+    storage::new_dictionary(ROLES).unwrap_or_default();
+    storage::new_dictionary(PAYEE_SHARES).unwrap_or_default();
+    storage::new_dictionary(RELEASED).unwrap_or_default();
+    storage::new_dictionary(EARNED).unwrap_or_default();
+    storage::new_dictionary(COLLECTIONS).unwrap_or_default();
+    storage::new_dictionary(BOX_OWNERS).unwrap_or_default();
+    storage::new_dictionary(REMOVED_ITEMS).unwrap_or_default();
+    storage::new_dictionary(ENTROPY_ROUNDS).unwrap_or_default();
+
+    init_events();
+
+    store_payees(&payees, &shares);
+    update_collection(nft_collection, Some(identifier_mode));
+
+    if runtime::get_key(PURSE).is_none() {
+        runtime::put_key(PURSE, system::create_purse().into());
+    }
+
+    // prize funds are kept apart from sale revenue
+    if runtime::get_key(PRIZE_PURSE).is_none() {
+        runtime::put_key(PRIZE_PURSE, system::create_purse().into());
+    }
 }
 
 #[no_mangle]
 pub extern "C" fn set_rarity() {
-    check_admin_account();
+    check_role(ROLE_INVENTORY_MANAGER);
     let item_index: u64 = runtime::get_named_arg(ITEM_INDEX);
     let rarity: u64 = runtime::get_named_arg(RARITY);
 
-    if rarity > 2 {
+    if rarity >= RARITY_LEVELS {
         runtime::revert(Error::RarityLevelNotFound);
     }
 
@@ -226,24 +703,367 @@ pub extern "C" fn set_rarity() {
         .unwrap()
         .unwrap_or_revert_with(Error::ItemNotFound);
 
+    if is_removed(item_index) {
+        runtime::revert(Error::ItemNotFound);
+    }
+
+    let item_owners = utils::get_uref(ITEM_OWNERS);
+    let owner: Option<AccountHash> = storage
+        ::dictionary_get::<AccountHash>(item_owners, &item_index.to_string())
+        .unwrap_or_revert();
+
+    // only unassigned items sit in a pool
+    if item.rarity != rarity && owner.is_none() {
+        pool
+            ::remove_pool_item(&mut CasperStore, item.rarity, item_index)
+            .map_err(Error::from)
+            .unwrap_or_revert();
+        pool::push_pool_item(&mut CasperStore, rarity, item_index);
+    }
+
     storage::dictionary_put(items, &item_index.to_string(), Item {
         id: item.id,
         rarity,
-        token_id: item.token_id,
         name: item.name,
+        prize: item.prize,
     });
+
+    emit(&(LootboxEvent::SetRarity { item_id: item_index, rarity }));
 }
 
+// inventory manager function
+/// Takes an unassigned item out of the draw pool and returns its prize to `recipient`.
 #[no_mangle]
-pub extern "C" fn withdraw() {
+pub extern "C" fn remove_item() {
+    check_role(ROLE_INVENTORY_MANAGER);
+    let item_index: u64 = runtime::get_named_arg(ITEM_INDEX);
+    let recipient: Key = runtime::get_named_arg(RECIPIENT);
+
+    let items: URef = utils::get_uref(ITEMS);
+    let item: Item = storage
+        ::dictionary_get::<Item>(items, &item_index.to_string())
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::ItemNotFound);
+
+    if is_removed(item_index) {
+        runtime::revert(Error::ItemNotFound);
+    }
+
+    let item_owners = utils::get_uref(ITEM_OWNERS);
+    let owner: Option<AccountHash> = storage
+        ::dictionary_get::<AccountHash>(item_owners, &item_index.to_string())
+        .unwrap_or_revert();
+
+    if owner.is_some() {
+        runtime::revert(Error::ItemAssigned);
+    }
+
+    // items set aside for sold boxes cannot be pulled out from under them
+    if pool::remaining_stock(&CasperStore) == 0 {
+        runtime::revert(Error::InsufficientStock);
+    }
+
+    pool
+        ::remove_pool_item(&mut CasperStore, item.rarity, item_index)
+        .map_err(Error::from)
+        .unwrap_or_revert();
+    return_item(item_index, item.prize, recipient);
+}
+
+// inventory manager function
+/// Sweeps up to `limit` undrawn items back to `recipient` once the sale is over, leaving enough
+/// in the pools for the boxes that are sold but not revealed yet, so large inventories are
+/// reclaimed over several calls. A sale without an end never ends, so the admin has to close
+/// it with `set_sale_window` first.
+#[no_mangle]
+pub extern "C" fn reclaim_unsold() {
+    check_role(ROLE_INVENTORY_MANAGER);
+    let recipient: Key = runtime::get_named_arg(RECIPIENT);
+    let limit: u64 = runtime::get_named_arg(LIMIT);
+
+    let sale_end: Option<u64> = utils::read_from(SALE_END);
+    let now: u64 = runtime::get_blocktime().into();
+
+    if !sale_end.map_or(false, |end| now >= end) {
+        runtime::revert(Error::SaleNotEnded);
+    }
+
+    let item_ids: Vec<u64> = pool
+        ::take_unreserved_items(&mut CasperStore, limit)
+        .map_err(Error::from)
+        .unwrap_or_revert();
+    let items: URef = utils::get_uref(ITEMS);
+
+    for item_index in item_ids {
+        let item: Item = storage
+            ::dictionary_get::<Item>(items, &item_index.to_string())
+            .unwrap_or_revert()
+            .unwrap_or_revert_with(Error::ItemNotFound);
+
+        return_item(item_index, item.prize, recipient);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn add_collection() {
     check_admin_account();
+    let collection: Key = runtime::get_named_arg(COLLECTION);
+    let identifier_mode: u8 = runtime::get_named_arg(IDENTIFIER_MODE);
 
-    let key: Key = runtime::get_key(PURSE).unwrap_or_revert();
-    let contract_purse: URef = key.into_uref().unwrap_or_revert();
-    let owner: AccountHash = runtime::get_caller();
-    let balance: U512 = system::get_purse_balance(contract_purse).unwrap_or_revert();
+    update_collection(collection, Some(identifier_mode));
+}
+
+/// Stops new deposits from `collection`; items already deposited can still be claimed.
+#[no_mangle]
+pub extern "C" fn remove_collection() {
+    check_admin_account();
+    let collection: Key = runtime::get_named_arg(COLLECTION);
+
+    update_collection(collection, None);
+}
+
+#[no_mangle]
+pub extern "C" fn set_rarity_weights() {
+    check_admin_account();
+    let rarity_weights: Vec<u64> = runtime::get_named_arg(RARITY_WEIGHTS);
+
+    check_rarity_weights(&rarity_weights);
+
+    runtime::put_key(RARITY_WEIGHTS, storage::new_uref(rarity_weights.clone()).into());
+
+    emit(&(LootboxEvent::SetRarityWeights { rarity_weights }));
+}
+
+#[no_mangle]
+pub extern "C" fn set_purchase_limit() {
+    check_admin_account();
+    let max_purchases_per_account: u64 = runtime::get_named_arg(MAX_PURCHASES_PER_ACCOUNT);
+
+    runtime::put_key(
+        MAX_PURCHASES_PER_ACCOUNT,
+        storage::new_uref(max_purchases_per_account).into()
+    );
+
+    emit(&(LootboxEvent::SetPurchaseLimit { max_purchases_per_account }));
+}
+
+#[no_mangle]
+pub extern "C" fn set_allowlist_only() {
+    check_admin_account();
+    let allowlist_only: bool = runtime::get_named_arg(ALLOWLIST_ONLY);
+
+    CasperStore.set_allowlist_only(allowlist_only);
+
+    emit(&(LootboxEvent::SetAllowlistOnly { allowlist_only }));
+}
+
+#[no_mangle]
+pub extern "C" fn add_to_allowlist() {
+    check_admin_account();
+    let accounts: Vec<AccountHash> = runtime::get_named_arg(ACCOUNTS);
+
+    update_allowlist(accounts, true);
+}
+
+#[no_mangle]
+pub extern "C" fn remove_from_allowlist() {
+    check_admin_account();
+    let accounts: Vec<AccountHash> = runtime::get_named_arg(ACCOUNTS);
+
+    update_allowlist(accounts, false);
+}
+
+#[no_mangle]
+pub extern "C" fn set_sale_window() {
+    check_admin_account();
+    let sale_start: Option<u64> = runtime::get_named_arg(SALE_START);
+    let sale_end: Option<u64> = runtime::get_named_arg(SALE_END);
+
+    check_sale_window(sale_start, sale_end);
+
+    CasperStore.set_sale_window(sale_start, sale_end);
+
+    emit(&(LootboxEvent::SetSaleWindow { sale_start, sale_end }));
+}
+
+#[no_mangle]
+pub extern "C" fn pause() {
+    check_admin_account();
+
+    CasperStore.set_paused(true);
 
-    system::transfer_from_purse_to_account(contract_purse, owner, balance, None).unwrap();
+    emit(&LootboxEvent::Pause);
+}
+
+#[no_mangle]
+pub extern "C" fn unpause() {
+    check_admin_account();
+
+    CasperStore.set_paused(false);
+
+    emit(&LootboxEvent::Unpause);
+}
+
+#[no_mangle]
+pub extern "C" fn transfer_ownership() {
+    check_admin_account();
+    let new_owner: AccountHash = runtime::get_named_arg(NEW_OWNER);
+
+    runtime::put_key(PENDING_OWNER, storage::new_uref(Some(new_owner)).into());
+
+    emit(
+        &(LootboxEvent::OwnershipTransferStarted {
+            previous_owner: Key::Account(runtime::get_caller()),
+            new_owner: Key::Account(new_owner),
+        })
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn accept_ownership() {
+    let caller: AccountHash = runtime::get_caller();
+    let pending_owner: Option<AccountHash> = utils::read_from(PENDING_OWNER);
+
+    if pending_owner != Some(caller) {
+        runtime::revert(Error::NotPendingOwner);
+    }
+
+    let previous_owner: AccountHash = get_key(OWNER);
+
+    runtime::put_key(OWNER, storage::new_uref(caller).into());
+    runtime::put_key(PENDING_OWNER, storage::new_uref(None::<AccountHash>).into());
+
+    emit(
+        &(LootboxEvent::OwnershipTransferred {
+            previous_owner: Key::Account(previous_owner),
+            new_owner: Key::Account(caller),
+        })
+    );
+}
+
+#[no_mangle]
+pub extern "C" fn grant_role() {
+    check_admin_account();
+    let account: AccountHash = runtime::get_named_arg(ACCOUNT);
+    let role: u8 = runtime::get_named_arg(ROLE);
+
+    check_role_flag(role);
+
+    let roles: URef = utils::get_uref(ROLES);
+    let account_key = utils::to_dictionary_key(&account.value());
+    let account_roles: u8 = storage
+        ::dictionary_get::<u8>(roles, &account_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    storage::dictionary_put(roles, &account_key, account_roles | role);
+
+    emit(&(LootboxEvent::RoleGranted { account: Key::Account(account), role }));
+}
+
+#[no_mangle]
+pub extern "C" fn revoke_role() {
+    check_admin_account();
+    let account: AccountHash = runtime::get_named_arg(ACCOUNT);
+    let role: u8 = runtime::get_named_arg(ROLE);
+
+    check_role_flag(role);
+
+    let roles: URef = utils::get_uref(ROLES);
+    let account_key = utils::to_dictionary_key(&account.value());
+    let account_roles: u8 = storage
+        ::dictionary_get::<u8>(roles, &account_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    storage::dictionary_put(roles, &account_key, account_roles & !role);
+
+    emit(&(LootboxEvent::RoleRevoked { account: Key::Account(account), role }));
+}
+
+#[no_mangle]
+pub extern "C" fn set_payees() {
+    check_admin_account();
+    let payees: Vec<AccountHash> = runtime::get_named_arg(PAYEES);
+    let shares: Vec<u64> = runtime::get_named_arg(SHARES);
+
+    // revenue earned so far stays split under the previous shares,
+    // the new ones only apply to revenue earned from now on
+    settle_revenue();
+
+    let payee_shares: URef = utils::get_uref(PAYEE_SHARES);
+    let previous_payees: Vec<AccountHash> = utils::read_from(PAYEES);
+
+    for payee in previous_payees {
+        storage::dictionary_put(payee_shares, &utils::to_dictionary_key(&payee.value()), 0u64);
+    }
+
+    store_payees(&payees, &shares);
+
+    emit(&(LootboxEvent::SetPayees { payees, shares }));
+}
+
+#[no_mangle]
+pub extern "C" fn release() {
+    let payee: AccountHash = runtime::get_caller();
+    let payee_key = utils::to_dictionary_key(&payee.value());
+
+    let payee_shares: URef = utils::get_uref(PAYEE_SHARES);
+    let share: u64 = storage
+        ::dictionary_get::<u64>(payee_shares, &payee_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    // a former payee can still release what they earned before being removed
+    let earned: U512 = storage
+        ::dictionary_get::<U512>(utils::get_uref(EARNED), &payee_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if share == 0 && earned.is_zero() {
+        runtime::revert(Error::NotPayee);
+    }
+
+    let released: URef = utils::get_uref(RELEASED);
+    let already_released: U512 = storage
+        ::dictionary_get::<U512>(released, &payee_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    let amount: U512 = (earned + revenue_share(share)).saturating_sub(already_released);
+
+    if amount.is_zero() {
+        runtime::revert(Error::NothingToRelease);
+    }
+
+    storage::dictionary_put(released, &payee_key, already_released + amount);
+
+    let total_released: U512 = utils::read_from(TOTAL_RELEASED);
+    runtime::put_key(TOTAL_RELEASED, storage::new_uref(total_released + amount).into());
+
+    pay_out(Key::Account(payee), amount);
+
+    emit(&(LootboxEvent::Release { payee: Key::Account(payee), amount }));
+}
+
+#[no_mangle]
+pub extern "C" fn withdraw() {
+    check_role(ROLE_TREASURER);
+
+    let amount: U512 = runtime::get_named_arg(AMOUNT);
+    let target: Key = runtime::get_named_arg(TARGET);
+
+    // the owner gets whatever part of the revenue is not assigned to payees
+    let total_withdrawn: U512 = utils::read_from(TOTAL_WITHDRAWN);
+    let owner_earned: U512 = utils::read_from(OWNER_EARNED);
+    let available: U512 = (owner_earned + owner_revenue_share()).saturating_sub(total_withdrawn);
+
+    if amount > available {
+        runtime::revert(Error::InsufficientBalance);
+    }
+
+    runtime::put_key(TOTAL_WITHDRAWN, storage::new_uref(total_withdrawn + amount).into());
+
+    pay_out(target, amount);
+
+    emit(&(LootboxEvent::Withdraw { recipient: target, amount }));
 }
 
 #[no_mangle]
@@ -258,11 +1078,40 @@ pub extern "C" fn call() {
     let max_lootboxes: u64 = runtime::get_named_arg(MAX_LOOTBOXES);
     let max_items: u64 = runtime::get_named_arg(MAX_ITEMS);
     let storage_key: ContractHash = runtime::get_named_arg(STORAGE_KEY);
+    let reveal_window: u64 = runtime::get_named_arg(REVEAL_WINDOW);
+    let reveal_delay: u64 = runtime::get_named_arg(REVEAL_DELAY);
+    let rarity_weights: Vec<u64> = runtime::get_named_arg(RARITY_WEIGHTS);
+    let max_purchases_per_account: u64 = runtime::get_named_arg(MAX_PURCHASES_PER_ACCOUNT);
+    let allowlist_only: bool = runtime::get_named_arg(ALLOWLIST_ONLY);
+    let sale_start: Option<u64> = runtime::get_named_arg(SALE_START);
+    let sale_end: Option<u64> = runtime::get_named_arg(SALE_END);
+    let payees: Vec<AccountHash> = runtime::get_named_arg(PAYEES);
+    let shares: Vec<u64> = runtime::get_named_arg(SHARES);
+    let payment_token: Option<Key> = runtime::get_named_arg(PAYMENT_TOKEN);
+    let identifier_mode: u8 = runtime::get_named_arg(IDENTIFIER_MODE);
+    let sealed_boxes: bool = runtime::get_named_arg(SEALED_BOXES);
+
+    check_sale_window(sale_start, sale_end);
+    check_payees(&payees, &shares);
+
+    check_rarity_weights(&rarity_weights);
+
+    // a commitment has to be revealable at some point before it expires
+    if reveal_delay >= reveal_window {
+        runtime::revert(Error::InvalidRevealDelay);
+    }
 
     // init
     let item_count: u64 = 0u64;
     let lootbox_count: u64 = 0u64;
     let deposited_item_count: u64 = 0u64;
+    let escrowed_amount: U512 = U512::zero();
+    let reserved_item_count: u64 = 0u64;
+    let paused: bool = false;
+    let pending_owner: Option<AccountHash> = None;
+    let total_revenue: U512 = U512::zero();
+    let no_payees: Vec<AccountHash> = Vec::new();
+    let item_pool_sizes: Vec<u64> = vec![0u64; RARITY_LEVELS as usize];
 
     //utils
     let owner: AccountHash = runtime::get_caller();
@@ -273,7 +1122,6 @@ pub extern "C" fn call() {
     named_keys.insert(NAME.to_string(), storage::new_uref(name.clone()).into());
     named_keys.insert(DESCRIPTION.to_string(), storage::new_uref(description.clone()).into());
     named_keys.insert(ASSET.to_string(), storage::new_uref(asset.clone()).into());
-    named_keys.insert(NFT_COLLECTION.to_string(), storage::new_uref(nft_collection.clone()).into());
     named_keys.insert(OWNER.to_string(), storage::new_uref(owner.clone()).into());
     named_keys.insert(LOOTBOX_PRICE.to_string(), storage::new_uref(lootbox_price.clone()).into());
     named_keys.insert(
@@ -290,85 +1138,402 @@ pub extern "C" fn call() {
     );
 
     named_keys.insert(STORAGE_KEY.to_string(), storage::new_uref(storage_key.clone()).into());
+    named_keys.insert(REVEAL_WINDOW.to_string(), storage::new_uref(reveal_window.clone()).into());
+    named_keys.insert(REVEAL_DELAY.to_string(), storage::new_uref(reveal_delay).into());
+    named_keys.insert(
+        ENTROPY.to_string(),
+        storage::new_uref(sha3_256(&[&owner.value(), &now.to_be_bytes()])).into()
+    );
+    named_keys.insert(ENTROPY_ROUND_COUNT.to_string(), storage::new_uref(0u64).into());
+    named_keys.insert(
+        ESCROWED_AMOUNT.to_string(),
+        storage::new_uref(escrowed_amount.clone()).into()
+    );
+    named_keys.insert(RARITY_WEIGHTS.to_string(), storage::new_uref(rarity_weights.clone()).into());
+    named_keys.insert(
+        ITEM_POOL_SIZES.to_string(),
+        storage::new_uref(item_pool_sizes.clone()).into()
+    );
+    named_keys.insert(
+        RESERVED_ITEM_COUNT.to_string(),
+        storage::new_uref(reserved_item_count.clone()).into()
+    );
+    named_keys.insert(
+        MAX_PURCHASES_PER_ACCOUNT.to_string(),
+        storage::new_uref(max_purchases_per_account.clone()).into()
+    );
+    named_keys.insert(ALLOWLIST_ONLY.to_string(), storage::new_uref(allowlist_only.clone()).into());
+    named_keys.insert(SALE_START.to_string(), storage::new_uref(sale_start.clone()).into());
+    named_keys.insert(SALE_END.to_string(), storage::new_uref(sale_end.clone()).into());
+    named_keys.insert(PAUSED.to_string(), storage::new_uref(paused.clone()).into());
+    named_keys.insert(PENDING_OWNER.to_string(), storage::new_uref(pending_owner.clone()).into());
+    named_keys.insert(TOTAL_REVENUE.to_string(), storage::new_uref(total_revenue.clone()).into());
+    named_keys.insert(TOTAL_RELEASED.to_string(), storage::new_uref(total_revenue.clone()).into());
+    named_keys.insert(TOTAL_WITHDRAWN.to_string(), storage::new_uref(total_revenue.clone()).into());
+    named_keys.insert(OWNER_EARNED.to_string(), storage::new_uref(total_revenue.clone()).into());
+    named_keys.insert(SETTLED_REVENUE.to_string(), storage::new_uref(total_revenue.clone()).into());
+    named_keys.insert(PAYEES.to_string(), storage::new_uref(no_payees.clone()).into());
+    named_keys.insert(PAYMENT_TOKEN.to_string(), storage::new_uref(payment_token.clone()).into());
+    named_keys.insert(SEALED_BOXES.to_string(), storage::new_uref(sealed_boxes).into());
+
+    // entrypoints
+    // only the token argument matching the collection's identifier mode is read
+    let add_item_entry_point = EntryPoint::new(
+        ENTRY_POINT_ADD_ITEM,
+        vec![
+            Parameter::new(COLLECTION, CLType::Key),
+            Parameter::new(ITEM_NAME, CLType::String),
+            Parameter::new(TOKEN_ID, CLType::U64),
+            Parameter::new(TOKEN_HASH, CLType::String),
+            Parameter::new(RARITY, CLType::U64)
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let add_items_entry_point = EntryPoint::new(
+        ENTRY_POINT_ADD_ITEMS,
+        vec![
+            Parameter::new(COLLECTION, CLType::Key),
+            Parameter::new(ITEM_NAMES, CLType::List(Box::new(CLType::String))),
+            Parameter::new(TOKEN_IDS, CLType::List(Box::new(CLType::U64))),
+            Parameter::new(TOKEN_HASHES, CLType::List(Box::new(CLType::String))),
+            Parameter::new(RARITIES, CLType::List(Box::new(CLType::U64)))
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let add_native_prize_entry_point = EntryPoint::new(
+        ENTRY_POINT_ADD_NATIVE_PRIZE,
+        vec![
+            Parameter::new(SOURCE_PURSE, CLType::URef),
+            Parameter::new(AMOUNT, CLType::U512),
+            Parameter::new(ITEM_NAME, CLType::String),
+            Parameter::new(RARITY, CLType::U64)
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let add_token_prize_entry_point = EntryPoint::new(
+        ENTRY_POINT_ADD_TOKEN_PRIZE,
+        vec![
+            Parameter::new(TOKEN, CLType::Key),
+            Parameter::new(AMOUNT, CLType::U256),
+            Parameter::new(ITEM_NAME, CLType::String),
+            Parameter::new(RARITY, CLType::U64)
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let init_entry_point = EntryPoint::new(
+        ENTRY_POINT_INIT,
+        vec![
+            Parameter::new(PAYEES, CLType::List(Box::new(CLType::ByteArray(32)))),
+            Parameter::new(SHARES, CLType::List(Box::new(CLType::U64))),
+            Parameter::new(NFT_COLLECTION, CLType::Key),
+            Parameter::new(IDENTIFIER_MODE, CLType::U8)
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let purchase_entry_point = EntryPoint::new(
+        ENTRY_POINT_PURCHASE,
+        vec![
+            Parameter::new(PAYMENT_PURSE, CLType::URef),
+            Parameter::new(COMMITMENT, CLType::ByteArray(32)),
+            Parameter::new(QUANTITY, CLType::U64)
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let transfer_box_entry_point = EntryPoint::new(
+        ENTRY_POINT_TRANSFER_BOX,
+        vec![Parameter::new(BOX_ID, CLType::U64), Parameter::new(RECIPIENT, CLType::ByteArray(32))],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let open_box_entry_point = EntryPoint::new(
+        ENTRY_POINT_OPEN_BOX,
+        vec![
+            Parameter::new(BOX_ID, CLType::U64),
+            Parameter::new(COMMITMENT, CLType::ByteArray(32))
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let claim_entry_point = EntryPoint::new(
+        ENTRY_POINT_CLAIM,
+        vec![Parameter::new(ITEM_INDEX, CLType::U64)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let claim_all_entry_point = EntryPoint::new(
+        ENTRY_POINT_CLAIM_ALL,
+        vec![],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let get_price_entry_point = EntryPoint::new(
+        ENTRY_POINT_GET_PRICE,
+        vec![],
+        CLType::U512,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let get_payment_token_entry_point = EntryPoint::new(
+        ENTRY_POINT_GET_PAYMENT_TOKEN,
+        vec![],
+        CLType::Option(Box::new(CLType::Key)),
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let get_remaining_stock_entry_point = EntryPoint::new(
+        ENTRY_POINT_GET_REMAINING_STOCK,
+        vec![],
+        CLType::U64,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let set_rarity_entry_point = EntryPoint::new(
+        ENTRY_POINT_SET_RARITY,
+        vec![Parameter::new(ITEM_INDEX, CLType::U64), Parameter::new(RARITY, CLType::U64)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let remove_item_entry_point = EntryPoint::new(
+        ENTRY_POINT_REMOVE_ITEM,
+        vec![Parameter::new(ITEM_INDEX, CLType::U64), Parameter::new(RECIPIENT, CLType::Key)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let reclaim_unsold_entry_point = EntryPoint::new(
+        ENTRY_POINT_RECLAIM_UNSOLD,
+        vec![Parameter::new(RECIPIENT, CLType::Key), Parameter::new(LIMIT, CLType::U64)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let withdraw_entry_point = EntryPoint::new(
+        ENTRY_POINT_WITHDRAW,
+        vec![Parameter::new(AMOUNT, CLType::U512), Parameter::new(TARGET, CLType::Key)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let reveal_entry_point = EntryPoint::new(
+        ENTRY_POINT_REVEAL,
+        vec![Parameter::new(SECRET, CLType::String)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let cancel_purchase_entry_point = EntryPoint::new(
+        ENTRY_POINT_CANCEL_PURCHASE,
+        vec![],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let set_rarity_weights_entry_point = EntryPoint::new(
+        ENTRY_POINT_SET_RARITY_WEIGHTS,
+        vec![Parameter::new(RARITY_WEIGHTS, CLType::List(Box::new(CLType::U64)))],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let set_purchase_limit_entry_point = EntryPoint::new(
+        ENTRY_POINT_SET_PURCHASE_LIMIT,
+        vec![Parameter::new(MAX_PURCHASES_PER_ACCOUNT, CLType::U64)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let set_allowlist_only_entry_point = EntryPoint::new(
+        ENTRY_POINT_SET_ALLOWLIST_ONLY,
+        vec![Parameter::new(ALLOWLIST_ONLY, CLType::Bool)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let add_to_allowlist_entry_point = EntryPoint::new(
+        ENTRY_POINT_ADD_TO_ALLOWLIST,
+        vec![Parameter::new(ACCOUNTS, CLType::List(Box::new(CLType::ByteArray(32))))],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let remove_from_allowlist_entry_point = EntryPoint::new(
+        ENTRY_POINT_REMOVE_FROM_ALLOWLIST,
+        vec![Parameter::new(ACCOUNTS, CLType::List(Box::new(CLType::ByteArray(32))))],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
 
-    // entrypoints
-    let add_item_entry_point = EntryPoint::new(
-        ENTRY_POINT_ADD_ITEM,
+    let set_sale_window_entry_point = EntryPoint::new(
+        ENTRY_POINT_SET_SALE_WINDOW,
         vec![
-            Parameter::new(ITEM_NAME, CLType::String),
-            Parameter::new(TOKEN_ID, CLType::U64),
-            Parameter::new(RARITY, CLType::U64)
+            Parameter::new(SALE_START, CLType::Option(Box::new(CLType::U64))),
+            Parameter::new(SALE_END, CLType::Option(Box::new(CLType::U64)))
         ],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let init_entry_point = EntryPoint::new(
-        ENTRY_POINT_INIT,
+    let pause_entry_point = EntryPoint::new(
+        ENTRY_POINT_PAUSE,
         vec![],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let purchase_entry_point = EntryPoint::new(
-        ENTRY_POINT_PURCHASE,
+    let unpause_entry_point = EntryPoint::new(
+        ENTRY_POINT_UNPAUSE,
         vec![],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let claim_entry_point = EntryPoint::new(
-        ENTRY_POINT_CLAIM,
-        vec![Parameter::new(ITEM_INDEX, CLType::U64)],
+    let transfer_ownership_entry_point = EntryPoint::new(
+        ENTRY_POINT_TRANSFER_OWNERSHIP,
+        vec![Parameter::new(NEW_OWNER, CLType::ByteArray(32))],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let get_price_entry_point = EntryPoint::new(
-        ENTRY_POINT_GET_PRICE,
+    let accept_ownership_entry_point = EntryPoint::new(
+        ENTRY_POINT_ACCEPT_OWNERSHIP,
         vec![],
-        CLType::U512,
+        CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let get_purse_entry_point = EntryPoint::new(
-        ENTRY_POINT_GET_PURSE,
-        vec![],
+    let grant_role_entry_point = EntryPoint::new(
+        ENTRY_POINT_GRANT_ROLE,
+        vec![Parameter::new(ACCOUNT, CLType::ByteArray(32)), Parameter::new(ROLE, CLType::U8)],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let set_rarity_entry_point = EntryPoint::new(
-        ENTRY_POINT_SET_RARITY,
-        vec![Parameter::new(ITEM_INDEX, CLType::U64), Parameter::new(RARITY, CLType::U64)],
+    let revoke_role_entry_point = EntryPoint::new(
+        ENTRY_POINT_REVOKE_ROLE,
+        vec![Parameter::new(ACCOUNT, CLType::ByteArray(32)), Parameter::new(ROLE, CLType::U8)],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
-    let withdraw_entry_point = EntryPoint::new(
-        ENTRY_POINT_WITHDRAW,
+    let set_payees_entry_point = EntryPoint::new(
+        ENTRY_POINT_SET_PAYEES,
+        vec![
+            Parameter::new(PAYEES, CLType::List(Box::new(CLType::ByteArray(32)))),
+            Parameter::new(SHARES, CLType::List(Box::new(CLType::U64)))
+        ],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let release_entry_point = EntryPoint::new(
+        ENTRY_POINT_RELEASE,
         vec![],
         CLType::URef,
         EntryPointAccess::Public,
         EntryPointType::Contract
     );
 
+    let add_collection_entry_point = EntryPoint::new(
+        ENTRY_POINT_ADD_COLLECTION,
+        vec![Parameter::new(COLLECTION, CLType::Key), Parameter::new(IDENTIFIER_MODE, CLType::U8)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
+    let remove_collection_entry_point = EntryPoint::new(
+        ENTRY_POINT_REMOVE_COLLECTION,
+        vec![Parameter::new(COLLECTION, CLType::Key)],
+        CLType::URef,
+        EntryPointAccess::Public,
+        EntryPointType::Contract
+    );
+
     let mut entry_points = EntryPoints::new();
     entry_points.add_entry_point(add_item_entry_point);
+    entry_points.add_entry_point(add_items_entry_point);
+    entry_points.add_entry_point(add_native_prize_entry_point);
+    entry_points.add_entry_point(add_token_prize_entry_point);
     entry_points.add_entry_point(init_entry_point);
     entry_points.add_entry_point(purchase_entry_point);
+    entry_points.add_entry_point(transfer_box_entry_point);
+    entry_points.add_entry_point(open_box_entry_point);
     entry_points.add_entry_point(claim_entry_point);
+    entry_points.add_entry_point(claim_all_entry_point);
     entry_points.add_entry_point(get_price_entry_point);
-    entry_points.add_entry_point(get_purse_entry_point);
+    entry_points.add_entry_point(get_payment_token_entry_point);
+    entry_points.add_entry_point(get_remaining_stock_entry_point);
     entry_points.add_entry_point(set_rarity_entry_point);
+    entry_points.add_entry_point(remove_item_entry_point);
+    entry_points.add_entry_point(reclaim_unsold_entry_point);
     entry_points.add_entry_point(withdraw_entry_point);
+    entry_points.add_entry_point(reveal_entry_point);
+    entry_points.add_entry_point(cancel_purchase_entry_point);
+    entry_points.add_entry_point(set_rarity_weights_entry_point);
+    entry_points.add_entry_point(set_purchase_limit_entry_point);
+    entry_points.add_entry_point(set_allowlist_only_entry_point);
+    entry_points.add_entry_point(add_to_allowlist_entry_point);
+    entry_points.add_entry_point(remove_from_allowlist_entry_point);
+    entry_points.add_entry_point(set_sale_window_entry_point);
+    entry_points.add_entry_point(pause_entry_point);
+    entry_points.add_entry_point(unpause_entry_point);
+    entry_points.add_entry_point(transfer_ownership_entry_point);
+    entry_points.add_entry_point(accept_ownership_entry_point);
+    entry_points.add_entry_point(grant_role_entry_point);
+    entry_points.add_entry_point(revoke_role_entry_point);
+    entry_points.add_entry_point(set_payees_entry_point);
+    entry_points.add_entry_point(release_entry_point);
+    entry_points.add_entry_point(add_collection_entry_point);
+    entry_points.add_entry_point(remove_collection_entry_point);
 
     // contract design
     let str1 = name.clone() + "_" + &now.to_string();
@@ -388,7 +1553,16 @@ pub extern "C" fn call() {
 
     runtime::put_key(&contract_hash_text.to_string(), contract_hash.into());
 
-    runtime::call_contract::<()>(contract_hash, ENTRY_POINT_INIT, runtime_args! {});
+    runtime::call_contract::<()>(
+        contract_hash,
+        ENTRY_POINT_INIT,
+        runtime_args! {
+        PAYEES => payees,
+        SHARES => shares,
+        NFT_COLLECTION => nft_collection,
+        IDENTIFIER_MODE => identifier_mode,
+    }
+    );
 
     runtime::call_contract::<()>(
         storage_key,
@@ -399,52 +1573,591 @@ pub extern "C" fn call() {
     )
 }
 
-pub fn check_admin_account() {
-    let admin: AccountHash = get_key(OWNER);
+/// Moves the caller's tokens into the contract and adds them to the draw pool.
+/// Any failure reverts the whole deploy, so a batch is deposited entirely or not at all.
+fn deposit_items(
+    collection: Key,
+    token_ids: Vec<TokenIdentifier>,
+    item_names: Vec<String>,
+    rarities: Vec<u64>
+) {
+    if rarities.iter().any(|rarity| *rarity >= RARITY_LEVELS) {
+        runtime::revert(Error::RarityLevelNotFound);
+    }
+
+    // CEP-78 identifies a calling contract by its package hash, as CEP-18 does
+    let contract_key: Key = utils::get_current_package_hash().into();
+    let caller: AccountHash = runtime::get_caller();
+    let mut deposited_item_count: u64 = utils::read_from(DEPOSITED_ITEM_COUNT);
+    let max_items: u64 = utils::read_from(MAX_ITEMS);
+
+    if deposited_item_count.saturating_add(token_ids.len() as u64) > max_items {
+        runtime::revert(Error::MaxItemCount);
+    }
+
+    let collection_hash: ContractHash = get_collection_hash(collection);
+
+    let is_approved: bool = is_approved_for_all(
+        collection_hash,
+        caller.into(),
+        contract_key
+    );
+
+    if !is_approved {
+        runtime::revert(Error::NotApproved);
+    }
+
+    let items_dict: URef = *runtime::get_key(ITEMS).unwrap().as_uref().unwrap();
+
+    for ((token_id, item_name), rarity) in token_ids
+        .into_iter()
+        .zip(item_names.into_iter())
+        .zip(rarities.into_iter()) {
+        // check owner is caller
+        transfer(collection_hash, caller.into(), contract_key, &token_id);
+
+        storage::dictionary_put(items_dict, &deposited_item_count.to_string(), Item {
+            id: deposited_item_count.into(),
+            rarity,
+            name: item_name.clone(),
+            prize: Prize::Nft { collection, token_id: token_id.clone() },
+        });
+
+        pool::push_pool_item(&mut CasperStore, rarity, deposited_item_count);
+
+        emit(
+            &(LootboxEvent::AddItem {
+                item_id: deposited_item_count,
+                token_id: token_id.to_string(),
+                rarity,
+                name: item_name,
+                collection,
+            })
+        );
+
+        deposited_item_count += 1;
+    }
+
+    runtime::put_key(DEPOSITED_ITEM_COUNT, storage::new_uref(deposited_item_count).into());
+}
+
+/// Stores a currency prize that has already been escrowed and adds it to the draw pool.
+fn store_prize(prize: Prize, item_name: String, rarity: u64) -> u64 {
+    if rarity >= RARITY_LEVELS {
+        runtime::revert(Error::RarityLevelNotFound);
+    }
+
+    let item_id: u64 = utils::read_from(DEPOSITED_ITEM_COUNT);
+    let max_items: u64 = utils::read_from(MAX_ITEMS);
+
+    if item_id >= max_items {
+        runtime::revert(Error::MaxItemCount);
+    }
+
+    let items_dict: URef = utils::get_uref(ITEMS);
+    storage::dictionary_put(items_dict, &item_id.to_string(), Item {
+        id: item_id,
+        rarity,
+        name: item_name,
+        prize,
+    });
+
+    pool::push_pool_item(&mut CasperStore, rarity, item_id);
+
+    runtime::put_key(DEPOSITED_ITEM_COUNT, storage::new_uref(item_id + 1).into());
+
+    item_id
+}
+
+/// Marks an item as claimed and pays its prize out to the winner.
+fn deliver_item(item_index: u64, to_account: AccountHash) {
+    let items: URef = *runtime::get_key(ITEMS).unwrap().as_uref().unwrap();
+    let data: Item = storage
+        ::dictionary_get::<Item>(items, &item_index.to_string())
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(Error::ItemNotFound);
+
+    let token_id: Option<String> = pay_prize(data.prize, Key::Account(to_account));
+
+    emit(
+        &(LootboxEvent::Claim {
+            owner: Key::Account(to_account),
+            item_id: item_index,
+            token_id,
+        })
+    );
+}
+
+/// Marks an item that has already left the draw pool as removed and sends its prize back.
+fn return_item(item_index: u64, prize: Prize, recipient: Key) {
+    let removed_items: URef = utils::get_uref(REMOVED_ITEMS);
+    storage::dictionary_put(removed_items, &item_index.to_string(), true);
+
+    pay_prize(prize, recipient);
+
+    emit(&(LootboxEvent::RemoveItem { item_id: item_index, recipient }));
+}
+
+fn is_removed(item_index: u64) -> bool {
+    let removed_items: URef = utils::get_uref(REMOVED_ITEMS);
+
+    storage
+        ::dictionary_get::<bool>(removed_items, &item_index.to_string())
+        .unwrap_or_revert()
+        .unwrap_or_default()
+}
+
+/// Sends a prize out of the contract, returning the token id for NFT prizes.
+fn pay_prize(prize: Prize, recipient: Key) -> Option<String> {
+    match prize {
+        Prize::Nft { collection, token_id } => {
+            let collection_hash: ContractHash = get_collection_hash(collection);
+            let contract_key: Key = utils::get_current_package_hash().into();
+
+            transfer(collection_hash, contract_key, recipient, &token_id);
+
+            Some(token_id.to_string())
+        }
+        Prize::Native(amount) => {
+            let prize_purse: URef = utils::get_uref(PRIZE_PURSE);
+            transfer_from_purse(prize_purse, recipient, amount);
+
+            None
+        }
+        Prize::Cep18 { token, amount } => {
+            let token_hash: ContractHash = get_token_hash(token);
+            cep18_transfer(token_hash, recipient, amount);
+
+            None
+        }
+    }
+}
+
+pub fn check_sale_window(sale_start: Option<u64>, sale_end: Option<u64>) {
+    sale::check_sale_window(sale_start, sale_end).map_err(Error::from).unwrap_or_revert();
+}
+
+/// Reverts unless purchases are currently allowed.
+pub fn check_sale_open() {
+    let now: u64 = runtime::get_blocktime().into();
+
+    sale::check_sale_open(&CasperStore, now).map_err(Error::from).unwrap_or_revert();
+}
+
+fn update_allowlist(accounts: Vec<AccountHash>, allowed: bool) {
+    for account in accounts.iter() {
+        CasperStore.set_allowlisted(*account, allowed);
+    }
+
+    emit(&(LootboxEvent::UpdateAllowlist { accounts, allowed }));
+}
+
+fn get_collection_hash(collection: Key) -> ContractHash {
+    collection.into_hash().map(ContractHash::new).unwrap_or_revert_with(Error::InvalidCollection)
+}
+
+fn get_token_hash(token: Key) -> ContractHash {
+    token.into_hash().map(ContractHash::new).unwrap_or_revert_with(Error::InvalidToken)
+}
+
+/// Returns the identifier mode of an allowed collection.
+fn get_identifier_mode(collection: Key) -> u8 {
+    let collection_hash: ContractHash = get_collection_hash(collection);
+    let collection_key = utils::to_dictionary_key(&collection_hash.value());
+    let collections: URef = utils::get_uref(COLLECTIONS);
+
+    storage
+        ::dictionary_get::<Option<u8>>(collections, &collection_key)
+        .unwrap_or_revert()
+        .flatten()
+        .unwrap_or_revert_with(Error::CollectionNotAllowed)
+}
+
+/// Allows deposits from `collection` in the given identifier mode, or disallows them on `None`.
+fn update_collection(collection: Key, identifier_mode: Option<u8>) {
+    let collection_hash: ContractHash = get_collection_hash(collection);
+
+    if let Some(mode) = identifier_mode {
+        if mode != IDENTIFIER_MODE_ORDINAL && mode != IDENTIFIER_MODE_HASH {
+            runtime::revert(Error::InvalidIdentifierMode);
+        }
+    }
+
+    let collections: URef = utils::get_uref(COLLECTIONS);
+    storage::dictionary_put(
+        collections,
+        &utils::to_dictionary_key(&collection_hash.value()),
+        identifier_mode
+    );
+
+    match identifier_mode {
+        Some(identifier_mode) =>
+            emit(&(LootboxEvent::AddCollection { collection, identifier_mode })),
+        None => emit(&(LootboxEvent::RemoveCollection { collection })),
+    }
+}
+
+fn check_no_pending_commitment(commitments: URef, account_key: &str) {
+    let pending: Option<Commitment> = storage
+        ::dictionary_get::<Commitment>(commitments, account_key)
+        .unwrap_or_revert();
+
+    if let Some(commitment) = pending {
+        if commitment.status == COMMITMENT_PENDING {
+            runtime::revert(Error::CommitmentPending);
+        }
+    }
+}
+
+fn store_commitment(
+    commitments: URef,
+    account_key: &str,
+    hash: [u8; 32],
+    amount: U512,
+    quantity: u64,
+    box_id: Option<u64>
+) {
+    let now: u64 = runtime::get_blocktime().into();
+    let reveal_window: u64 = utils::read_from(REVEAL_WINDOW);
+
+    storage::dictionary_put(commitments, account_key, Commitment {
+        hash,
+        committed_at: now,
+        expires_at: now.saturating_add(reveal_window),
+        amount,
+        status: COMMITMENT_PENDING,
+        quantity,
+        box_id,
+    });
+}
+
+/// Folds `parts` into the entropy accumulator and records its new value as a round.
+fn stir_entropy(parts: &[&[u8]]) {
+    let entropy: [u8; 32] = utils::read_from(ENTROPY);
+
+    let mut inputs: Vec<&[u8]> = vec![&entropy[..]];
+    inputs.extend_from_slice(parts);
+
+    let entropy = sha3_256(&inputs);
+    runtime::put_key(ENTROPY, storage::new_uref(entropy).into());
+
+    // every value the accumulator takes is kept, so a reveal can look up the one it is bound to
+    let entropy_rounds: URef = utils::get_uref(ENTROPY_ROUNDS);
+    let round_count: u64 = utils::read_from(ENTROPY_ROUND_COUNT);
+    let time: u64 = runtime::get_blocktime().into();
+    storage::dictionary_put(entropy_rounds, &round_count.to_string(), EntropyRound {
+        time,
+        entropy,
+    });
+    runtime::put_key(ENTROPY_ROUND_COUNT, storage::new_uref(round_count + 1).into());
+}
+
+/// The accumulator as the last call made strictly before block time `time` left it.
+/// Rounds are stored in time order, so this is a binary search over them.
+fn entropy_before(time: u64) -> [u8; 32] {
+    let entropy_rounds: URef = utils::get_uref(ENTROPY_ROUNDS);
+    let read_round = |round: u64| -> EntropyRound {
+        storage
+            ::dictionary_get::<EntropyRound>(entropy_rounds, &round.to_string())
+            .unwrap_or_revert()
+            .unwrap_or_revert_with(Error::FatalError)
+    };
+
+    // first round at or after `time`
+    let mut low: u64 = 0;
+    let mut high: u64 = utils::read_from(ENTROPY_ROUND_COUNT);
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        if read_round(middle).time < time {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    // the commitment itself stirred the accumulator, so there is always an earlier round
+    if low == 0 {
+        runtime::revert(Error::FatalError);
+    }
+
+    read_round(low - 1).entropy
+}
+
+/// Reverts unless sealed boxes are enabled and `account` holds box `box_id`.
+fn check_box_owner(box_id: u64, account: AccountHash) {
+    let sealed_boxes: bool = utils::read_from(SEALED_BOXES);
+
+    if !sealed_boxes {
+        runtime::revert(Error::SealedBoxesDisabled);
+    }
+
+    let box_owners: URef = utils::get_uref(BOX_OWNERS);
+    let owner: Option<AccountHash> = storage
+        ::dictionary_get::<Option<AccountHash>>(box_owners, &box_id.to_string())
+        .unwrap_or_revert()
+        .flatten();
+
+    if owner != Some(account) {
+        runtime::revert(Error::NotBoxOwner);
+    }
+}
+
+fn check_role_flag(role: u8) {
+    if role != ROLE_INVENTORY_MANAGER && role != ROLE_TREASURER {
+        runtime::revert(Error::InvalidRole);
+    }
+}
+
+/// Reverts unless the caller is the owner or has been granted `role`.
+pub fn check_role(role: u8) {
     let caller = runtime::get_caller();
-    if admin != caller {
+    let admin: AccountHash = get_key(OWNER);
+
+    if admin == caller {
+        return;
+    }
+
+    let roles: URef = utils::get_uref(ROLES);
+    let account_roles: u8 = storage
+        ::dictionary_get::<u8>(roles, &utils::to_dictionary_key(&caller.value()))
+        .unwrap_or_revert()
+        .unwrap_or_default();
+
+    if account_roles & role == 0 {
         runtime::revert(Error::AdminError);
     }
 }
 
-fn bytes_to_u64(bytes: &[u8]) -> u64 {
-    let mut result: u64 = 0;
-    for i in 0..8 {
-        result |= (bytes[i] as u64) << ((7 - i) * 8);
+/// Revenue earned since the payees were last set.
+fn unsettled_revenue() -> U512 {
+    let total_revenue: U512 = utils::read_from(TOTAL_REVENUE);
+    let settled_revenue: U512 = utils::read_from(SETTLED_REVENUE);
+
+    total_revenue - settled_revenue
+}
+
+/// Part of the revenue earned since the payees were last set that belongs to a holder of
+/// `share` basis points.
+pub fn revenue_share(share: u64) -> U512 {
+    (unsettled_revenue() * U512::from(share)) / U512::from(BASIS_POINTS)
+}
+
+/// Part of the revenue earned since the payees were last set that is left once every payee
+/// took their share. Payee shares round down, so the remainders go to the owner instead of
+/// staying locked.
+fn owner_revenue_share() -> U512 {
+    let payees: Vec<AccountHash> = utils::read_from(PAYEES);
+    let payee_shares: URef = utils::get_uref(PAYEE_SHARES);
+
+    payees.iter().fold(unsettled_revenue(), |rest, payee| {
+        let share: u64 = storage
+            ::dictionary_get::<u64>(payee_shares, &utils::to_dictionary_key(&payee.value()))
+            .unwrap_or_revert()
+            .unwrap_or_default();
+        rest - revenue_share(share)
+    })
+}
+
+/// Credits the revenue earned since the payees were last set to the current payees and the
+/// owner, so that a change of shares does not rewrite what was already earned.
+fn settle_revenue() {
+    let payees: Vec<AccountHash> = utils::read_from(PAYEES);
+    let payee_shares: URef = utils::get_uref(PAYEE_SHARES);
+    let earned: URef = utils::get_uref(EARNED);
+
+    for payee in payees {
+        let payee_key = utils::to_dictionary_key(&payee.value());
+        let share: u64 = storage
+            ::dictionary_get::<u64>(payee_shares, &payee_key)
+            .unwrap_or_revert()
+            .unwrap_or_default();
+        let payee_earned: U512 = storage
+            ::dictionary_get::<U512>(earned, &payee_key)
+            .unwrap_or_revert()
+            .unwrap_or_default();
+        storage::dictionary_put(earned, &payee_key, payee_earned + revenue_share(share));
     }
-    result
+
+    let owner_earned: U512 = utils::read_from(OWNER_EARNED);
+    runtime::put_key(OWNER_EARNED, storage::new_uref(owner_earned + owner_revenue_share()).into());
+
+    let total_revenue: U512 = utils::read_from(TOTAL_REVENUE);
+    runtime::put_key(SETTLED_REVENUE, storage::new_uref(total_revenue).into());
 }
 
-pub fn get_random_item_id(max_items: u64, item_count: u64) -> u64 {
-    let now: u64 = runtime::get_blocktime().into();
-    let mut sha3 = Sha3::v256();
-    let input = alloc::format!("{}{}", now, item_count);
+/// Reverts unless every payee has a non-zero share and the shares fit in the revenue.
+pub fn check_payees(payees: &[AccountHash], shares: &[u64]) {
+    if payees.len() != shares.len() {
+        runtime::revert(Error::InvalidShares);
+    }
 
-    sha3.update(input.as_ref());
+    let mut total_shares: u64 = 0;
+    for (index, (payee, share)) in payees.iter().zip(shares.iter()).enumerate() {
+        if *share == 0 || payees[..index].contains(payee) {
+            runtime::revert(Error::InvalidShares);
+        }
+        total_shares = total_shares.saturating_add(*share);
+    }
 
-    let mut hash_bytes = [0u8; 32]; // SHA-3-256 for 32 bytes
-    sha3.finalize(&mut hash_bytes);
+    if total_shares > BASIS_POINTS {
+        runtime::revert(Error::InvalidShares);
+    }
+}
 
-    let hash_number = bytes_to_u64(&hash_bytes);
+fn store_payees(payees: &[AccountHash], shares: &[u64]) {
+    check_payees(payees, shares);
 
-    let item_id = hash_number % max_items;
+    let payee_shares: URef = utils::get_uref(PAYEE_SHARES);
 
-    let item_owners = *runtime::get_key(ITEM_OWNERS).unwrap().as_uref().unwrap();
+    for (payee, share) in payees.iter().zip(shares.iter()) {
+        storage::dictionary_put(payee_shares, &utils::to_dictionary_key(&payee.value()), *share);
+    }
 
-    let exist: Option<AccountHash> = storage
-        ::dictionary_get::<AccountHash>(item_owners, &item_id.to_string())
-        .unwrap();
+    runtime::put_key(PAYEES, storage::new_uref(payees.to_vec()).into());
+}
 
-    match exist {
-        Some(_) => {
-            return self::get_random_item_id(max_items, item_count + 1);
+/// Takes `amount` from the buyer, either in CSPR from the purse passed as `payment_purse`
+/// or in the payment token through an allowance the buyer gave this contract.
+fn collect_payment(buyer: AccountHash, amount: U512) {
+    let payment_token: Option<Key> = utils::read_from(PAYMENT_TOKEN);
+
+    match payment_token {
+        Some(token) => {
+            let token_hash: ContractHash = get_token_hash(token);
+            let token_amount: U256 = utils::u512_to_u256(amount);
+            let contract_key: Key = utils::get_current_package_hash().into();
+
+            let allowance: U256 = cep18_allowance(token_hash, Key::Account(buyer), contract_key);
+            let balance: U256 = cep18_balance_of(token_hash, Key::Account(buyer));
+
+            sale
+                ::check_funds(&[allowance, balance], token_amount)
+                .map_err(Error::from)
+                .unwrap_or_revert();
+
+            let balance_before: U256 = cep18_balance_of(token_hash, contract_key);
+            cep18_transfer_from(token_hash, Key::Account(buyer), contract_key, token_amount);
+            let balance_after: U256 = cep18_balance_of(token_hash, contract_key);
+
+            sale
+                ::check_received(balance_before, balance_after, token_amount)
+                .map_err(Error::from)
+                .unwrap_or_revert();
         }
-        _ => {
-            return item_id;
+        None => {
+            // the buyer funds a purse of their own and hands it over to be checked here
+            let payment_purse: URef = runtime::get_named_arg(PAYMENT_PURSE);
+            let payment: U512 = system
+                ::get_purse_balance(payment_purse)
+                .unwrap_or_revert_with(Error::InsufficientPayment);
+
+            sale::check_funds(&[payment], amount).map_err(Error::from).unwrap_or_revert();
+
+            // only what actually lands in the contract purse counts as this buyer's payment
+            let contract_purse: URef = utils::get_uref(PURSE);
+            let balance_before: U512 = system::get_purse_balance(contract_purse).unwrap_or_revert();
+            system
+                ::transfer_from_purse_to_purse(payment_purse, contract_purse, amount, None)
+                .unwrap_or_revert();
+            let balance_after: U512 = system::get_purse_balance(contract_purse).unwrap_or_revert();
+
+            sale
+                ::check_received(balance_before, balance_after, amount)
+                .map_err(Error::from)
+                .unwrap_or_revert();
+
+            // anything paid above the price goes straight back to the buyer
+            let excess: U512 = payment - amount;
+
+            if !excess.is_zero() {
+                system
+                    ::transfer_from_purse_to_account(payment_purse, buyer, excess, None)
+                    .unwrap_or_revert_with(Error::TransferFailed);
+            }
+        }
+    }
+
+    let account_payments: URef = utils::get_uref(ACCOUNT_PAYMENTS);
+    let account_key = utils::to_dictionary_key(&buyer.value());
+    let paid: U512 = storage
+        ::dictionary_get::<U512>(account_payments, &account_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    storage::dictionary_put(account_payments, &account_key, paid + amount);
+}
+
+/// Returns part of a payment to `buyer` and takes it off their recorded payments.
+fn refund_payment(buyer: AccountHash, amount: U512) {
+    if amount.is_zero() {
+        return;
+    }
+
+    pay_out(Key::Account(buyer), amount);
+
+    let account_payments: URef = utils::get_uref(ACCOUNT_PAYMENTS);
+    let account_key = utils::to_dictionary_key(&buyer.value());
+    let paid: U512 = storage
+        ::dictionary_get::<U512>(account_payments, &account_key)
+        .unwrap_or_revert()
+        .unwrap_or_default();
+    storage::dictionary_put(account_payments, &account_key, paid.saturating_sub(amount));
+}
+
+/// Pays `amount` of the sale currency out of the contract's own funds.
+fn pay_out(target: Key, amount: U512) {
+    let payment_token: Option<Key> = utils::read_from(PAYMENT_TOKEN);
+
+    match payment_token {
+        Some(token) => {
+            let token_hash: ContractHash = get_token_hash(token);
+
+            if target.into_account().is_none() && target.into_hash().is_none() {
+                runtime::revert(Error::InvalidTarget);
+            }
+
+            cep18_transfer(token_hash, target, utils::u512_to_u256(amount));
+        }
+        None => {
+            let contract_purse: URef = utils::get_uref(PURSE);
+            transfer_from_purse(contract_purse, target, amount);
+        }
+    }
+}
+
+/// Sends `amount` out of `purse` to an account's main purse or to another purse.
+fn transfer_from_purse(purse: URef, target: Key, amount: U512) {
+    match target {
+        Key::Account(account_hash) => {
+            system
+                ::transfer_from_purse_to_account(purse, account_hash, amount, None)
+                .unwrap_or_revert_with(Error::TransferFailed);
+        }
+        Key::URef(target_purse) => {
+            system
+                ::transfer_from_purse_to_purse(purse, target_purse, amount, None)
+                .unwrap_or_revert_with(Error::TransferFailed);
         }
+        _ => runtime::revert(Error::InvalidTarget),
+    }
+}
+
+pub fn check_admin_account() {
+    let admin: AccountHash = get_key(OWNER);
+    let caller = runtime::get_caller();
+    if admin != caller {
+        runtime::revert(Error::AdminError);
     }
 }
 
+pub fn check_rarity_weights(rarity_weights: &[u64]) {
+    draw
+        ::check_rarity_weights(rarity_weights, RARITY_LEVELS as usize)
+        .map_err(Error::from)
+        .unwrap_or_revert();
+}
+
 pub fn is_approved_for_all(contract_hash: ContractHash, owner: Key, operator: Key) -> bool {
     return runtime::call_contract::<bool>(
         contract_hash,
@@ -467,14 +2180,70 @@ pub fn is_approved_for_all(contract_hash: ContractHash, owner: Key, operator: Ke
 //     )
 // }
 
-pub fn transfer(contract_hash: ContractHash, sender: Key, recipient: Key, token_id: u64) -> () {
+pub fn cep18_allowance(contract_hash: ContractHash, owner: Key, spender: Key) -> U256 {
+    runtime::call_contract::<U256>(
+        contract_hash,
+        "allowance",
+        runtime_args! {
+            "owner" => owner,
+            "spender" => spender,
+        }
+    )
+}
+
+pub fn cep18_balance_of(contract_hash: ContractHash, address: Key) -> U256 {
+    runtime::call_contract::<U256>(
+        contract_hash,
+        "balance_of",
+        runtime_args! {
+            "address" => address,
+        }
+    )
+}
+
+pub fn cep18_transfer_from(contract_hash: ContractHash, owner: Key, recipient: Key, amount: U256) {
+    runtime::call_contract::<()>(
+        contract_hash,
+        "transfer_from",
+        runtime_args! {
+            "owner" => owner,
+            "recipient" => recipient,
+            "amount" => amount,
+        }
+    )
+}
+
+pub fn cep18_transfer(contract_hash: ContractHash, recipient: Key, amount: U256) {
     runtime::call_contract::<()>(
         contract_hash,
         "transfer",
         runtime_args! {
-          "token_id" => token_id,
-          "source_key" => sender,
-          "target_key" => recipient,
-      }
+            "recipient" => recipient,
+            "amount" => amount,
+        }
     )
 }
+
+pub fn transfer(
+    contract_hash: ContractHash,
+    sender: Key,
+    recipient: Key,
+    token_id: &TokenIdentifier
+) -> () {
+    let args = match token_id {
+        TokenIdentifier::Index(token_id) =>
+            runtime_args! {
+              "token_id" => *token_id,
+              "source_key" => sender,
+              "target_key" => recipient,
+          },
+        TokenIdentifier::Hash(token_hash) =>
+            runtime_args! {
+              "token_hash" => token_hash.clone(),
+              "source_key" => sender,
+              "target_key" => recipient,
+          },
+    };
+
+    runtime::call_contract::<()>(contract_hash, "transfer", args)
+}
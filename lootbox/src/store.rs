@@ -0,0 +1,142 @@
+use alloc::{ format, string::{ String, ToString }, vec::Vec };
+
+use casper_contract::{ contract_api::{ runtime, storage }, unwrap_or_revert::UnwrapOrRevert };
+use casper_types::{ account::AccountHash, bytesrepr::{ FromBytes, ToBytes }, CLTyped };
+use lootbox_core::Store;
+
+use crate::{
+    lootbox::{
+        ACCOUNT_PURCHASES,
+        ALLOWLIST,
+        ALLOWLIST_ONLY,
+        CLAIMED_ITEMS,
+        ITEM_OWNERS,
+        ITEM_POOL,
+        ITEM_POOL_SIZES,
+        ITEM_POSITIONS,
+        PAUSED,
+        PENDING_CLAIMS,
+        RESERVED_ITEM_COUNT,
+        SALE_END,
+        SALE_START,
+    },
+    utils,
+};
+
+/// The lootbox core state kept in the contract's named keys and dictionaries.
+pub struct CasperStore;
+
+fn pool_key(rarity: u64, position: u64) -> String {
+    format!("{}_{}", rarity, position)
+}
+
+fn account_key(account: AccountHash) -> String {
+    utils::to_dictionary_key(&account.value())
+}
+
+fn get<T: CLTyped + FromBytes>(dictionary: &str, key: &str) -> Option<T> {
+    storage::dictionary_get::<T>(utils::get_uref(dictionary), key).unwrap_or_revert()
+}
+
+fn put<T: CLTyped + ToBytes>(dictionary: &str, key: &str, value: T) {
+    storage::dictionary_put(utils::get_uref(dictionary), key, value);
+}
+
+impl Store for CasperStore {
+    fn item_pool_sizes(&self) -> Vec<u64> {
+        utils::read_from(ITEM_POOL_SIZES)
+    }
+
+    fn set_item_pool_sizes(&mut self, item_pool_sizes: Vec<u64>) {
+        runtime::put_key(ITEM_POOL_SIZES, storage::new_uref(item_pool_sizes).into());
+    }
+
+    fn pool_item(&self, rarity: u64, position: u64) -> Option<u64> {
+        get(ITEM_POOL, &pool_key(rarity, position))
+    }
+
+    fn set_pool_item(&mut self, rarity: u64, position: u64, item_id: u64) {
+        put(ITEM_POOL, &pool_key(rarity, position), item_id);
+    }
+
+    fn item_position(&self, item_id: u64) -> Option<u64> {
+        get(ITEM_POSITIONS, &item_id.to_string())
+    }
+
+    fn set_item_position(&mut self, item_id: u64, position: u64) {
+        put(ITEM_POSITIONS, &item_id.to_string(), position);
+    }
+
+    fn reserved_item_count(&self) -> u64 {
+        utils::read_from(RESERVED_ITEM_COUNT)
+    }
+
+    fn set_reserved_item_count(&mut self, reserved_item_count: u64) {
+        runtime::put_key(RESERVED_ITEM_COUNT, storage::new_uref(reserved_item_count).into());
+    }
+
+    fn purchase_count(&self, account: AccountHash) -> u64 {
+        get(ACCOUNT_PURCHASES, &account_key(account)).unwrap_or_default()
+    }
+
+    fn set_purchase_count(&mut self, account: AccountHash, purchase_count: u64) {
+        put(ACCOUNT_PURCHASES, &account_key(account), purchase_count);
+    }
+
+    fn item_owner(&self, item_id: u64) -> Option<AccountHash> {
+        get(ITEM_OWNERS, &item_id.to_string())
+    }
+
+    fn set_item_owner(&mut self, item_id: u64, owner: AccountHash) {
+        put(ITEM_OWNERS, &item_id.to_string(), owner);
+    }
+
+    fn is_claimed(&self, item_id: u64) -> bool {
+        get(CLAIMED_ITEMS, &item_id.to_string()).unwrap_or_default()
+    }
+
+    fn set_claimed(&mut self, item_id: u64) {
+        put(CLAIMED_ITEMS, &item_id.to_string(), true);
+    }
+
+    fn pending_claims(&self, account: AccountHash) -> Vec<u64> {
+        get(PENDING_CLAIMS, &account_key(account)).unwrap_or_default()
+    }
+
+    fn set_pending_claims(&mut self, account: AccountHash, item_ids: Vec<u64>) {
+        put(PENDING_CLAIMS, &account_key(account), item_ids);
+    }
+
+    fn is_paused(&self) -> bool {
+        utils::read_from(PAUSED)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        runtime::put_key(PAUSED, storage::new_uref(paused).into());
+    }
+
+    fn sale_window(&self) -> (Option<u64>, Option<u64>) {
+        (utils::read_from(SALE_START), utils::read_from(SALE_END))
+    }
+
+    fn set_sale_window(&mut self, sale_start: Option<u64>, sale_end: Option<u64>) {
+        runtime::put_key(SALE_START, storage::new_uref(sale_start).into());
+        runtime::put_key(SALE_END, storage::new_uref(sale_end).into());
+    }
+
+    fn allowlist_only(&self) -> bool {
+        utils::read_from(ALLOWLIST_ONLY)
+    }
+
+    fn set_allowlist_only(&mut self, allowlist_only: bool) {
+        runtime::put_key(ALLOWLIST_ONLY, storage::new_uref(allowlist_only).into());
+    }
+
+    fn is_allowlisted(&self, account: AccountHash) -> bool {
+        get(ALLOWLIST, &account_key(account)).unwrap_or_default()
+    }
+
+    fn set_allowlisted(&mut self, account: AccountHash, allowed: bool) {
+        put(ALLOWLIST, &account_key(account), allowed);
+    }
+}
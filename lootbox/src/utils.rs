@@ -5,7 +5,17 @@ use casper_contract::{
     contract_api::storage,
     unwrap_or_revert::UnwrapOrRevert,
 };
-use casper_types::{ system::CallStackElement, U512, CLTyped, URef, bytesrepr::FromBytes };
+use casper_types::{
+    system::CallStackElement,
+    U256,
+    U512,
+    CLTyped,
+    URef,
+    bytesrepr::FromBytes,
+    ContractPackageHash,
+};
+
+use alloc::string::String;
 
 use crate::enums::Address;
 use crate::error::Error;
@@ -27,6 +37,15 @@ pub fn get_current_address() -> Address {
     }
 }
 
+/// Package hash of the running contract, which is how token contracts see it as a caller.
+pub fn get_current_package_hash() -> ContractPackageHash {
+    let call_stack_element = runtime::get_call_stack().into_iter().rev().next().unwrap_or_revert();
+    match call_stack_element {
+        CallStackElement::StoredContract { contract_package_hash, .. } => contract_package_hash,
+        _ => runtime::revert(Error::FatalError),
+    }
+}
+
 pub fn get_key<T: FromBytes + CLTyped>(name: &str) -> T {
     let key = runtime
         ::get_key(name)
@@ -50,3 +69,24 @@ pub(crate) fn get_uref(name: &str) -> URef {
     let key = runtime::get_key(name).ok_or(Error::FatalError).unwrap_or_revert();
     key.try_into().unwrap_or_revert()
 }
+
+/// Encodes raw bytes as lowercase hex, short enough to be used as a dictionary item key.
+pub(crate) fn to_dictionary_key(bytes: &[u8]) -> String {
+    let mut key = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        key.push_str(&alloc::format!("{:02x}", byte));
+    }
+    key
+}
+
+/// Narrows a CSPR-sized amount to a token amount, reverting if it does not fit.
+pub(crate) fn u512_to_u256(amount: U512) -> U256 {
+    let mut bytes = [0u8; 64];
+    amount.to_little_endian(&mut bytes);
+
+    if bytes[32..].iter().any(|byte| *byte != 0) {
+        runtime::revert(Error::AmountOverflow);
+    }
+
+    U256::from_little_endian(&bytes[..32])
+}
@@ -0,0 +1,142 @@
+use alloc::vec::Vec;
+use tiny_keccak::{ Hasher, Sha3 };
+
+use crate::Error;
+
+pub fn bytes_to_u64(bytes: &[u8]) -> u64 {
+    let mut result: u64 = 0;
+    for i in 0..8 {
+        result |= (bytes[i] as u64) << ((7 - i) * 8);
+    }
+    result
+}
+
+pub fn sha3_256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut sha3 = Sha3::v256();
+    for part in parts {
+        sha3.update(part);
+    }
+
+    let mut hash_bytes = [0u8; 32]; // SHA-3-256 for 32 bytes
+    sha3.finalize(&mut hash_bytes);
+    hash_bytes
+}
+
+/// Derives an independent random number for each `label` and `nonce` from the reveal seed.
+pub fn random_u64(seed: &[u8; 32], label: &[u8], nonce: u64) -> u64 {
+    bytes_to_u64(&sha3_256(&[seed, label, &nonce.to_be_bytes()]))
+}
+
+/// Checks that there is one weight per rarity level and that the weights can be summed
+/// without overflowing, which [`pick_rarity`] relies on.
+pub fn check_rarity_weights(rarity_weights: &[u64], rarity_levels: usize) -> Result<(), Error> {
+    if rarity_weights.len() != rarity_levels {
+        return Err(Error::InvalidRarityWeights);
+    }
+
+    rarity_weights
+        .iter()
+        .try_fold(0u64, |total, weight| total.checked_add(*weight))
+        .ok_or(Error::InvalidRarityWeights)?;
+
+    Ok(())
+}
+
+/// Picks a rarity level by weight among the levels that still have unassigned items.
+/// Weights of exhausted levels are shared out over the rest; if every level left is
+/// weighted zero, they are all treated as equally likely. The weights are expected to
+/// have passed [`check_rarity_weights`].
+pub fn pick_rarity(rarity_weights: &[u64], item_pool_sizes: &[u64], random: u64) -> Option<u64> {
+    let available: Vec<u64> = (0..item_pool_sizes.len() as u64)
+        .filter(|rarity| item_pool_sizes[*rarity as usize] > 0)
+        .collect();
+
+    if available.is_empty() {
+        return None;
+    }
+
+    let total_weight: u64 = available
+        .iter()
+        .map(|rarity| rarity_weights[*rarity as usize])
+        .sum();
+
+    if total_weight == 0 {
+        return Some(available[(random % (available.len() as u64)) as usize]);
+    }
+
+    let mut target = random % total_weight;
+    for rarity in available {
+        let weight = rarity_weights[rarity as usize];
+        if target < weight {
+            return Some(rarity);
+        }
+        target -= weight;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_to_u64_reads_big_endian() {
+        assert_eq!(bytes_to_u64(&[0, 0, 0, 0, 0, 0, 1, 2, 0xff]), 258);
+    }
+
+    #[test]
+    fn random_u64_depends_on_label_and_nonce() {
+        let seed = sha3_256(&[&b"seed"[..]]);
+
+        assert_eq!(random_u64(&seed, b"item", 0), random_u64(&seed, b"item", 0));
+        assert_ne!(random_u64(&seed, b"item", 0), random_u64(&seed, b"item", 1));
+        assert_ne!(random_u64(&seed, b"item", 0), random_u64(&seed, b"rarity", 0));
+    }
+
+    #[test]
+    fn check_rarity_weights_rejects_overflowing_sums() {
+        assert_eq!(check_rarity_weights(&[70, 25, 5], 3), Ok(()));
+        assert_eq!(check_rarity_weights(&[u64::MAX, 0, 0], 3), Ok(()));
+        assert_eq!(check_rarity_weights(&[70, 25], 3), Err(Error::InvalidRarityWeights));
+        assert_eq!(
+            check_rarity_weights(&[u64::MAX, 1, 0], 3),
+            Err(Error::InvalidRarityWeights)
+        );
+        assert_eq!(
+            check_rarity_weights(&[u64::MAX / 2, u64::MAX / 2, 2], 3),
+            Err(Error::InvalidRarityWeights)
+        );
+    }
+
+    #[test]
+    fn pick_rarity_follows_weights() {
+        let weights = [70, 25, 5];
+        let sizes = [1, 1, 1];
+
+        assert_eq!(pick_rarity(&weights, &sizes, 0), Some(0));
+        assert_eq!(pick_rarity(&weights, &sizes, 69), Some(0));
+        assert_eq!(pick_rarity(&weights, &sizes, 70), Some(1));
+        assert_eq!(pick_rarity(&weights, &sizes, 95), Some(2));
+        assert_eq!(pick_rarity(&weights, &sizes, 100), Some(0));
+    }
+
+    #[test]
+    fn pick_rarity_skips_exhausted_levels() {
+        let weights = [70, 25, 5];
+        let sizes = [0, 1, 1];
+
+        assert_eq!(pick_rarity(&weights, &sizes, 0), Some(1));
+        assert_eq!(pick_rarity(&weights, &sizes, 25), Some(2));
+        assert_eq!(pick_rarity(&weights, &[0, 0, 0], 0), None);
+    }
+
+    #[test]
+    fn pick_rarity_falls_back_to_uniform_when_weights_are_zero() {
+        let weights = [10, 0, 0];
+        let sizes = [0, 1, 1];
+
+        assert_eq!(pick_rarity(&weights, &sizes, 0), Some(1));
+        assert_eq!(pick_rarity(&weights, &sizes, 1), Some(2));
+    }
+}
@@ -0,0 +1,20 @@
+/// Failures of the core state transitions; the contract maps each one onto its own error code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    ItemNotFound,
+    ClaimNotFound,
+    NotItemOwner,
+    AlreadyClaimed,
+    InsufficientStock,
+    ItemsReserved,
+    PurchaseLimitExceeded,
+    LootboxLimit,
+    InvalidQuantity,
+    InsufficientPayment,
+    NotAllowlisted,
+    SaleNotStarted,
+    SaleEnded,
+    Paused,
+    InvalidSaleWindow,
+    InvalidRarityWeights,
+}
@@ -0,0 +1,260 @@
+use alloc::vec::Vec;
+use casper_types::account::AccountHash;
+
+use crate::{ pool::{ draw_item, remaining_stock }, Error, Store };
+
+/// Counts `quantity` boxes against the buyer's limit and sets their items aside until they are
+/// revealed or cancelled. A `max_purchases_per_account` of zero means there is no limit.
+pub fn reserve_purchase<S: Store>(
+    store: &mut S,
+    buyer: AccountHash,
+    max_purchases_per_account: u64,
+    quantity: u64,
+    items_per_lootbox: u64
+) -> Result<(), Error> {
+    let purchase_count = store.purchase_count(buyer).saturating_add(quantity);
+
+    if max_purchases_per_account > 0 && purchase_count > max_purchases_per_account {
+        return Err(Error::PurchaseLimitExceeded);
+    }
+
+    let reserved_items = quantity.checked_mul(items_per_lootbox).ok_or(Error::InsufficientStock)?;
+
+    if remaining_stock(store) < reserved_items {
+        return Err(Error::InsufficientStock);
+    }
+
+    store.set_purchase_count(buyer, purchase_count);
+
+    let reserved_item_count = store.reserved_item_count();
+    store.set_reserved_item_count(reserved_item_count + reserved_items);
+
+    Ok(())
+}
+
+/// Undoes [`reserve_purchase`] for `quantity` cancelled boxes.
+pub fn release_purchase<S: Store>(
+    store: &mut S,
+    buyer: AccountHash,
+    quantity: u64,
+    items_per_lootbox: u64
+) {
+    let purchase_count = store.purchase_count(buyer);
+    store.set_purchase_count(buyer, purchase_count.saturating_sub(quantity));
+
+    release_items(store, quantity * items_per_lootbox);
+}
+
+/// Gives `item_total` reserved items back to the unsold stock.
+pub fn release_items<S: Store>(store: &mut S, item_total: u64) {
+    let reserved_item_count = store.reserved_item_count();
+    store.set_reserved_item_count(reserved_item_count.saturating_sub(item_total));
+}
+
+/// Draws up to `item_total` reserved items for `owner` and queues them for claiming.
+/// `item_count` is the number of items assigned so far; the drawn items are returned
+/// in draw order. Only the drawn items leave the reservation, so if the pools run dry
+/// first, the caller has to release and refund the shortfall.
+pub fn assign_items<S: Store>(
+    store: &mut S,
+    seed: &[u8; 32],
+    owner: AccountHash,
+    rarity_weights: &[u64],
+    item_total: u64,
+    item_count: u64
+) -> Result<Vec<u64>, Error> {
+    let mut won_items: Vec<u64> = Vec::new();
+
+    for nonce in item_count..item_count + item_total {
+        let item_id = match draw_item(store, seed, rarity_weights, nonce)? {
+            Some(item_id) => item_id,
+            None => {
+                break;
+            }
+        };
+
+        store.set_item_owner(item_id, owner);
+        won_items.push(item_id);
+    }
+
+    release_items(store, won_items.len() as u64);
+
+    let mut unclaimed_items = store.pending_claims(owner);
+    unclaimed_items.extend(won_items.iter().copied());
+    store.set_pending_claims(owner, unclaimed_items);
+
+    Ok(won_items)
+}
+
+/// Marks a single item as claimed by its owner.
+pub fn take_claim<S: Store>(
+    store: &mut S,
+    account: AccountHash,
+    item_id: u64
+) -> Result<(), Error> {
+    let owner = store.item_owner(item_id).ok_or(Error::ClaimNotFound)?;
+
+    if owner != account {
+        return Err(Error::NotItemOwner);
+    }
+
+    if store.is_claimed(item_id) {
+        return Err(Error::AlreadyClaimed);
+    }
+
+    let mut unclaimed_items = store.pending_claims(account);
+    unclaimed_items.retain(|unclaimed_item| *unclaimed_item != item_id);
+    store.set_pending_claims(account, unclaimed_items);
+
+    store.set_claimed(item_id);
+
+    Ok(())
+}
+
+/// Marks every item `account` has not claimed yet as claimed and returns them.
+pub fn take_all_claims<S: Store>(store: &mut S, account: AccountHash) -> Result<Vec<u64>, Error> {
+    let unclaimed_items = store.pending_claims(account);
+
+    if unclaimed_items.is_empty() {
+        return Err(Error::ClaimNotFound);
+    }
+
+    store.set_pending_claims(account, Vec::new());
+
+    for item_id in unclaimed_items.iter() {
+        store.set_claimed(*item_id);
+    }
+
+    Ok(unclaimed_items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ draw::sha3_256, memory::MemoryStore, pool::push_pool_item };
+
+    const BUYER: AccountHash = AccountHash::new([1u8; 32]);
+    const OTHER: AccountHash = AccountHash::new([2u8; 32]);
+    const WEIGHTS: [u64; 3] = [1, 1, 1];
+
+    fn stocked_store(item_total: u64) -> MemoryStore {
+        let mut store = MemoryStore::new(3);
+        for item_id in 0..item_total {
+            push_pool_item(&mut store, item_id % 3, item_id);
+        }
+        store
+    }
+
+    #[test]
+    fn reserve_purchase_sets_items_aside() {
+        let mut store = stocked_store(3);
+
+        reserve_purchase(&mut store, BUYER, 0, 1, 2).unwrap();
+
+        assert_eq!(store.reserved_item_count(), 2);
+        assert_eq!(store.purchase_count(BUYER), 1);
+        assert_eq!(remaining_stock(&store), 1);
+        assert_eq!(reserve_purchase(&mut store, OTHER, 0, 1, 2), Err(Error::InsufficientStock));
+        assert_eq!(store.purchase_count(OTHER), 0);
+    }
+
+    #[test]
+    fn reserve_purchase_enforces_the_account_limit() {
+        let mut store = stocked_store(3);
+
+        reserve_purchase(&mut store, BUYER, 1, 1, 1).unwrap();
+
+        assert_eq!(reserve_purchase(&mut store, BUYER, 1, 1, 1), Err(Error::PurchaseLimitExceeded));
+        assert_eq!(reserve_purchase(&mut store, OTHER, 1, 1, 1), Ok(()));
+    }
+
+    #[test]
+    fn reserve_purchase_counts_every_box_of_a_multi_purchase() {
+        let mut store = stocked_store(6);
+
+        assert_eq!(reserve_purchase(&mut store, BUYER, 2, 3, 1), Err(Error::PurchaseLimitExceeded));
+        assert_eq!(reserve_purchase(&mut store, BUYER, 0, 4, 2), Err(Error::InsufficientStock));
+        assert_eq!(store.purchase_count(BUYER), 0);
+
+        reserve_purchase(&mut store, BUYER, 3, 3, 2).unwrap();
+
+        assert_eq!(store.purchase_count(BUYER), 3);
+        assert_eq!(store.reserved_item_count(), 6);
+        assert_eq!(remaining_stock(&store), 0);
+    }
+
+    #[test]
+    fn release_purchase_restores_stock_and_limit() {
+        let mut store = stocked_store(3);
+        reserve_purchase(&mut store, BUYER, 1, 1, 3).unwrap();
+
+        release_purchase(&mut store, BUYER, 1, 3);
+
+        assert_eq!(store.reserved_item_count(), 0);
+        assert_eq!(reserve_purchase(&mut store, BUYER, 1, 1, 3), Ok(()));
+    }
+
+    #[test]
+    fn assign_items_hands_reserved_items_to_the_buyer() {
+        let seed = sha3_256(&[&b"secret"[..]]);
+        let mut store = stocked_store(3);
+        reserve_purchase(&mut store, BUYER, 0, 1, 2).unwrap();
+
+        let won_items = assign_items(&mut store, &seed, BUYER, &WEIGHTS, 2, 0).unwrap();
+
+        assert_eq!(won_items.len(), 2);
+        assert_eq!(store.reserved_item_count(), 0);
+        assert_eq!(store.item_pool_sizes().iter().sum::<u64>(), 1);
+        assert_eq!(store.pending_claims(BUYER), won_items);
+        for item_id in won_items {
+            assert_eq!(store.item_owner(item_id), Some(BUYER));
+        }
+    }
+
+    #[test]
+    fn assign_items_only_releases_what_it_draws() {
+        let seed = sha3_256(&[&b"secret"[..]]);
+        let mut store = stocked_store(2);
+        store.set_reserved_item_count(3);
+
+        let won_items = assign_items(&mut store, &seed, BUYER, &WEIGHTS, 3, 0).unwrap();
+
+        assert_eq!(won_items.len(), 2);
+        assert_eq!(store.reserved_item_count(), 1);
+        assert_eq!(remaining_stock(&store), 0);
+
+        release_items(&mut store, 3 - (won_items.len() as u64));
+
+        assert_eq!(store.reserved_item_count(), 0);
+    }
+
+    #[test]
+    fn take_claim_checks_owner_and_double_claims() {
+        let seed = sha3_256(&[&b"secret"[..]]);
+        let mut store = stocked_store(3);
+        reserve_purchase(&mut store, BUYER, 0, 1, 2).unwrap();
+        let won_items = assign_items(&mut store, &seed, BUYER, &WEIGHTS, 2, 0).unwrap();
+        let unassigned = (0..3).find(|item_id| !won_items.contains(item_id)).unwrap();
+
+        assert_eq!(take_claim(&mut store, BUYER, unassigned), Err(Error::ClaimNotFound));
+        assert_eq!(take_claim(&mut store, OTHER, won_items[0]), Err(Error::NotItemOwner));
+
+        take_claim(&mut store, BUYER, won_items[0]).unwrap();
+
+        assert!(store.is_claimed(won_items[0]));
+        assert_eq!(store.pending_claims(BUYER), [won_items[1]]);
+        assert_eq!(take_claim(&mut store, BUYER, won_items[0]), Err(Error::AlreadyClaimed));
+    }
+
+    #[test]
+    fn take_all_claims_empties_the_pending_list() {
+        let seed = sha3_256(&[&b"secret"[..]]);
+        let mut store = stocked_store(3);
+        reserve_purchase(&mut store, BUYER, 0, 1, 3).unwrap();
+        let won_items = assign_items(&mut store, &seed, BUYER, &WEIGHTS, 3, 0).unwrap();
+
+        assert_eq!(take_all_claims(&mut store, BUYER), Ok(won_items.clone()));
+        assert!(won_items.iter().all(|item_id| store.is_claimed(*item_id)));
+        assert_eq!(take_all_claims(&mut store, BUYER), Err(Error::ClaimNotFound));
+    }
+}
@@ -0,0 +1,18 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Lootbox state transitions that do not depend on the Casper host.
+//!
+//! Everything here works against the [`Store`] trait, so the contract runs it on global state
+//! while tests run it on a [`memory::MemoryStore`].
+extern crate alloc;
+
+mod error;
+mod store;
+pub mod draw;
+pub mod ledger;
+pub mod memory;
+pub mod pool;
+pub mod sale;
+
+pub use error::Error;
+pub use store::Store;
@@ -0,0 +1,133 @@
+use alloc::{ collections::{ BTreeMap, BTreeSet }, vec, vec::Vec };
+use casper_types::account::AccountHash;
+
+use crate::Store;
+
+/// A [`Store`] backed by plain collections, for running the lootbox logic off-chain.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    item_pool_sizes: Vec<u64>,
+    item_pool: BTreeMap<(u64, u64), u64>,
+    item_positions: BTreeMap<u64, u64>,
+    reserved_item_count: u64,
+    account_purchases: BTreeMap<AccountHash, u64>,
+    item_owners: BTreeMap<u64, AccountHash>,
+    claimed_items: BTreeSet<u64>,
+    pending_claims: BTreeMap<AccountHash, Vec<u64>>,
+    paused: bool,
+    sale_window: (Option<u64>, Option<u64>),
+    allowlist_only: bool,
+    allowlist: BTreeSet<AccountHash>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store with one pool per rarity level.
+    pub fn new(rarity_levels: usize) -> Self {
+        MemoryStore {
+            item_pool_sizes: vec![0u64; rarity_levels],
+            ..Default::default()
+        }
+    }
+}
+
+impl Store for MemoryStore {
+    fn item_pool_sizes(&self) -> Vec<u64> {
+        self.item_pool_sizes.clone()
+    }
+
+    fn set_item_pool_sizes(&mut self, item_pool_sizes: Vec<u64>) {
+        self.item_pool_sizes = item_pool_sizes;
+    }
+
+    fn pool_item(&self, rarity: u64, position: u64) -> Option<u64> {
+        self.item_pool.get(&(rarity, position)).copied()
+    }
+
+    fn set_pool_item(&mut self, rarity: u64, position: u64, item_id: u64) {
+        self.item_pool.insert((rarity, position), item_id);
+    }
+
+    fn item_position(&self, item_id: u64) -> Option<u64> {
+        self.item_positions.get(&item_id).copied()
+    }
+
+    fn set_item_position(&mut self, item_id: u64, position: u64) {
+        self.item_positions.insert(item_id, position);
+    }
+
+    fn reserved_item_count(&self) -> u64 {
+        self.reserved_item_count
+    }
+
+    fn set_reserved_item_count(&mut self, reserved_item_count: u64) {
+        self.reserved_item_count = reserved_item_count;
+    }
+
+    fn purchase_count(&self, account: AccountHash) -> u64 {
+        self.account_purchases.get(&account).copied().unwrap_or_default()
+    }
+
+    fn set_purchase_count(&mut self, account: AccountHash, purchase_count: u64) {
+        self.account_purchases.insert(account, purchase_count);
+    }
+
+    fn item_owner(&self, item_id: u64) -> Option<AccountHash> {
+        self.item_owners.get(&item_id).copied()
+    }
+
+    fn set_item_owner(&mut self, item_id: u64, owner: AccountHash) {
+        self.item_owners.insert(item_id, owner);
+    }
+
+    fn is_claimed(&self, item_id: u64) -> bool {
+        self.claimed_items.contains(&item_id)
+    }
+
+    fn set_claimed(&mut self, item_id: u64) {
+        self.claimed_items.insert(item_id);
+    }
+
+    fn pending_claims(&self, account: AccountHash) -> Vec<u64> {
+        self.pending_claims.get(&account).cloned().unwrap_or_default()
+    }
+
+    fn set_pending_claims(&mut self, account: AccountHash, item_ids: Vec<u64>) {
+        self.pending_claims.insert(account, item_ids);
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    fn sale_window(&self) -> (Option<u64>, Option<u64>) {
+        self.sale_window
+    }
+
+    fn set_sale_window(&mut self, sale_start: Option<u64>, sale_end: Option<u64>) {
+        self.sale_window = (sale_start, sale_end);
+    }
+
+    fn allowlist_only(&self) -> bool {
+        self.allowlist_only
+    }
+
+    fn set_allowlist_only(&mut self, allowlist_only: bool) {
+        self.allowlist_only = allowlist_only;
+    }
+
+    fn is_allowlisted(&self, account: AccountHash) -> bool {
+        self.allowlist.contains(&account)
+    }
+
+    fn set_allowlisted(&mut self, account: AccountHash, allowed: bool) {
+        if allowed {
+            self.allowlist.insert(account);
+        } else {
+            self.allowlist.remove(&account);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use alloc::vec::Vec;
+
+use crate::{ draw::{ pick_rarity, random_u64 }, Error, Store };
+
+/// Number of deposited items that are neither assigned nor set aside for a sold box.
+pub fn remaining_stock<S: Store>(store: &S) -> u64 {
+    store.item_pool_sizes().iter().sum::<u64>().saturating_sub(store.reserved_item_count())
+}
+
+/// Adds an unassigned item to the pool of its rarity level.
+pub fn push_pool_item<S: Store>(store: &mut S, rarity: u64, item_id: u64) {
+    let mut item_pool_sizes = store.item_pool_sizes();
+    let position = item_pool_sizes[rarity as usize];
+
+    store.set_pool_item(rarity, position, item_id);
+    store.set_item_position(item_id, position);
+
+    item_pool_sizes[rarity as usize] = position + 1;
+    store.set_item_pool_sizes(item_pool_sizes);
+}
+
+/// Takes `item_id` out of the pool of its rarity level,
+/// moving the last entry of that pool into its slot.
+pub fn remove_pool_item<S: Store>(store: &mut S, rarity: u64, item_id: u64) -> Result<(), Error> {
+    let position = store.item_position(item_id).ok_or(Error::ItemNotFound)?;
+
+    swap_and_pop(store, rarity, position)?;
+    Ok(())
+}
+
+/// Removes the pool entry at `position` in constant time and returns the item stored there.
+pub fn swap_and_pop<S: Store>(store: &mut S, rarity: u64, position: u64) -> Result<u64, Error> {
+    let mut item_pool_sizes = store.item_pool_sizes();
+
+    let item_id = store.pool_item(rarity, position).ok_or(Error::ItemNotFound)?;
+    let last_position = item_pool_sizes[rarity as usize] - 1;
+
+    if position != last_position {
+        let last_item_id = store.pool_item(rarity, last_position).ok_or(Error::ItemNotFound)?;
+
+        store.set_pool_item(rarity, position, last_item_id);
+        store.set_item_position(last_item_id, position);
+    }
+
+    item_pool_sizes[rarity as usize] = last_position;
+    store.set_item_pool_sizes(item_pool_sizes);
+
+    Ok(item_id)
+}
+
+/// Takes up to `limit` items out of the pools, always leaving enough behind for the boxes that
+/// are sold but not revealed yet. Pools are emptied from the lowest rarity level up.
+pub fn take_unreserved_items<S: Store>(store: &mut S, limit: u64) -> Result<Vec<u64>, Error> {
+    let unreserved = remaining_stock(store);
+
+    if unreserved == 0 && store.reserved_item_count() > 0 {
+        return Err(Error::ItemsReserved);
+    }
+
+    let mut item_ids = Vec::new();
+
+    while (item_ids.len() as u64) < unreserved.min(limit) {
+        let item_pool_sizes = store.item_pool_sizes();
+        let rarity = item_pool_sizes
+            .iter()
+            .position(|pool_size| *pool_size > 0)
+            .ok_or(Error::ItemNotFound)?;
+
+        item_ids.push(swap_and_pop(store, rarity as u64, item_pool_sizes[rarity] - 1)?);
+    }
+
+    Ok(item_ids)
+}
+
+/// Draws an unassigned item, first choosing its rarity level by weight.
+pub fn draw_item<S: Store>(
+    store: &mut S,
+    seed: &[u8; 32],
+    rarity_weights: &[u64],
+    item_count: u64
+) -> Result<Option<u64>, Error> {
+    let item_pool_sizes = store.item_pool_sizes();
+    let random = random_u64(seed, b"rarity", item_count);
+
+    let rarity = match pick_rarity(rarity_weights, &item_pool_sizes, random) {
+        Some(rarity) => rarity,
+        None => {
+            return Ok(None);
+        }
+    };
+
+    get_random_item_id(store, seed, rarity, item_pool_sizes[rarity as usize], item_count).map(Some)
+}
+
+/// Fisher-Yates step over the pool of a rarity level: the drawn item leaves the pool,
+/// so every draw costs the same no matter how many items are already assigned.
+pub fn get_random_item_id<S: Store>(
+    store: &mut S,
+    seed: &[u8; 32],
+    rarity: u64,
+    pool_size: u64,
+    item_count: u64
+) -> Result<u64, Error> {
+    let position = random_u64(seed, b"item", item_count) % pool_size;
+
+    swap_and_pop(store, rarity, position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ draw::sha3_256, memory::MemoryStore };
+
+    fn pool(store: &MemoryStore, rarity: u64) -> Vec<u64> {
+        let size = store.item_pool_sizes()[rarity as usize];
+        (0..size).map(|position| store.pool_item(rarity, position).unwrap()).collect()
+    }
+
+    #[test]
+    fn swap_and_pop_moves_last_item_into_the_gap() {
+        let mut store = MemoryStore::new(3);
+        for item_id in 0..4 {
+            push_pool_item(&mut store, 0, item_id);
+        }
+
+        assert_eq!(swap_and_pop(&mut store, 0, 1), Ok(1));
+        assert_eq!(pool(&store, 0), [0, 3, 2]);
+        assert_eq!(store.item_position(3), Some(1));
+    }
+
+    #[test]
+    fn remove_pool_item_moves_items_between_levels() {
+        let mut store = MemoryStore::new(3);
+        push_pool_item(&mut store, 0, 0);
+        push_pool_item(&mut store, 0, 1);
+
+        remove_pool_item(&mut store, 0, 0).unwrap();
+        push_pool_item(&mut store, 2, 0);
+
+        assert_eq!(store.item_pool_sizes(), [1, 0, 1]);
+        assert_eq!(pool(&store, 0), [1]);
+        assert_eq!(pool(&store, 2), [0]);
+        assert_eq!(swap_and_pop(&mut store, 1, 0), Err(Error::ItemNotFound));
+    }
+
+    #[test]
+    fn take_unreserved_items_leaves_the_reserved_stock() {
+        let mut store = MemoryStore::new(3);
+        for item_id in 0..5 {
+            push_pool_item(&mut store, item_id % 2, item_id);
+        }
+        store.set_reserved_item_count(2);
+
+        assert_eq!(take_unreserved_items(&mut store, 2), Ok(vec![4, 2]));
+        assert_eq!(take_unreserved_items(&mut store, 10), Ok(vec![0]));
+        assert_eq!(store.item_pool_sizes(), [0, 2, 0]);
+        assert_eq!(take_unreserved_items(&mut store, 10), Err(Error::ItemsReserved));
+
+        store.set_reserved_item_count(0);
+        assert_eq!(take_unreserved_items(&mut store, 10), Ok(vec![3, 1]));
+        assert_eq!(take_unreserved_items(&mut store, 10), Ok(vec![]));
+    }
+
+    #[test]
+    fn get_random_item_id_is_deterministic_for_a_seed() {
+        let seed = sha3_256(&[&b"seed"[..]]);
+        let mut first = MemoryStore::new(3);
+        let mut second = MemoryStore::new(3);
+        for item_id in 0..10 {
+            push_pool_item(&mut first, 1, item_id);
+            push_pool_item(&mut second, 1, item_id);
+        }
+
+        let drawn = get_random_item_id(&mut first, &seed, 1, 10, 0).unwrap();
+
+        assert_eq!(get_random_item_id(&mut second, &seed, 1, 10, 0), Ok(drawn));
+        assert!(!pool(&first, 1).contains(&drawn));
+        assert_eq!(first.item_pool_sizes(), [0, 9, 0]);
+    }
+
+    #[test]
+    fn draw_item_drains_every_pool_exactly_once() {
+        let seed = sha3_256(&[&b"seed"[..]]);
+        let mut store = MemoryStore::new(3);
+        for item_id in 0..6 {
+            push_pool_item(&mut store, item_id % 3, item_id);
+        }
+
+        let mut drawn: Vec<u64> = (0..6)
+            .map(|item_count| draw_item(&mut store, &seed, &[5, 3, 1], item_count))
+            .map(|drawn_item| drawn_item.unwrap().unwrap())
+            .collect();
+        drawn.sort_unstable();
+
+        assert_eq!(drawn, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(draw_item(&mut store, &seed, &[5, 3, 1], 6), Ok(None));
+    }
+}
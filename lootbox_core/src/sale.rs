@@ -0,0 +1,154 @@
+use core::ops::Sub;
+
+use casper_types::{ account::AccountHash, U512 };
+
+use crate::{ Error, Store };
+
+/// Rejects a sale window that closes before it opens.
+pub fn check_sale_window(sale_start: Option<u64>, sale_end: Option<u64>) -> Result<(), Error> {
+    if let (Some(start), Some(end)) = (sale_start, sale_end) {
+        if start >= end {
+            return Err(Error::InvalidSaleWindow);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that purchases are allowed at block time `now`.
+pub fn check_sale_open<S: Store>(store: &S, now: u64) -> Result<(), Error> {
+    if store.is_paused() {
+        return Err(Error::Paused);
+    }
+
+    let (sale_start, sale_end) = store.sale_window();
+
+    if sale_start.map_or(false, |start| now < start) {
+        return Err(Error::SaleNotStarted);
+    }
+
+    if sale_end.map_or(false, |end| now >= end) {
+        return Err(Error::SaleEnded);
+    }
+
+    Ok(())
+}
+
+/// Checks that `buyer` may purchase while the sale is limited to the allowlist.
+pub fn check_allowlisted<S: Store>(store: &S, buyer: AccountHash) -> Result<(), Error> {
+    if store.allowlist_only() && !store.is_allowlisted(buyer) {
+        return Err(Error::NotAllowlisted);
+    }
+
+    Ok(())
+}
+
+/// Checks that `quantity` more boxes fit under `max_lootboxes`.
+pub fn check_quantity(lootbox_count: u64, max_lootboxes: u64, quantity: u64) -> Result<(), Error> {
+    if quantity == 0 {
+        return Err(Error::InvalidQuantity);
+    }
+
+    if lootbox_count.saturating_add(quantity) > max_lootboxes {
+        return Err(Error::LootboxLimit);
+    }
+
+    Ok(())
+}
+
+/// Price of `quantity` boxes.
+pub fn total_price(lootbox_price: U512, quantity: u64) -> Result<U512, Error> {
+    lootbox_price.checked_mul(U512::from(quantity)).ok_or(Error::InsufficientPayment)
+}
+
+/// Checks that every limit on what the buyer can hand over, such as a purse balance
+/// or a token allowance, covers `amount`.
+pub fn check_funds<T: Ord + Copy>(limits: &[T], amount: T) -> Result<(), Error> {
+    if limits.iter().any(|limit| *limit < amount) {
+        return Err(Error::InsufficientPayment);
+    }
+
+    Ok(())
+}
+
+/// Checks that the contract's balance grew by exactly `amount` over the payment transfer.
+pub fn check_received<T: Ord + Copy + Sub<Output = T>>(
+    balance_before: T,
+    balance_after: T,
+    amount: T
+) -> Result<(), Error> {
+    if balance_after < balance_before || balance_after - balance_before != amount {
+        return Err(Error::InsufficientPayment);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryStore;
+
+    const BUYER: AccountHash = AccountHash::new([1u8; 32]);
+
+    #[test]
+    fn check_sale_window_rejects_an_inverted_window() {
+        assert_eq!(check_sale_window(Some(10), Some(20)), Ok(()));
+        assert_eq!(check_sale_window(None, Some(20)), Ok(()));
+        assert_eq!(check_sale_window(Some(20), Some(20)), Err(Error::InvalidSaleWindow));
+    }
+
+    #[test]
+    fn check_sale_open_follows_the_window_and_pause() {
+        let mut store = MemoryStore::new(3);
+        store.set_sale_window(Some(10), Some(20));
+
+        assert_eq!(check_sale_open(&store, 9), Err(Error::SaleNotStarted));
+        assert_eq!(check_sale_open(&store, 10), Ok(()));
+        assert_eq!(check_sale_open(&store, 20), Err(Error::SaleEnded));
+
+        store.set_paused(true);
+
+        assert_eq!(check_sale_open(&store, 15), Err(Error::Paused));
+    }
+
+    #[test]
+    fn check_allowlisted_only_applies_in_allowlist_mode() {
+        let mut store = MemoryStore::new(3);
+
+        assert_eq!(check_allowlisted(&store, BUYER), Ok(()));
+
+        store.set_allowlist_only(true);
+
+        assert_eq!(check_allowlisted(&store, BUYER), Err(Error::NotAllowlisted));
+
+        store.set_allowlisted(BUYER, true);
+
+        assert_eq!(check_allowlisted(&store, BUYER), Ok(()));
+    }
+
+    #[test]
+    fn check_quantity_respects_the_lootbox_limit() {
+        assert_eq!(check_quantity(0, 3, 0), Err(Error::InvalidQuantity));
+        assert_eq!(check_quantity(1, 3, 2), Ok(()));
+        assert_eq!(check_quantity(1, 3, 3), Err(Error::LootboxLimit));
+        assert_eq!(check_quantity(1, 3, u64::MAX), Err(Error::LootboxLimit));
+    }
+
+    #[test]
+    fn payment_checks_need_the_full_amount() {
+        let price = U512::from(10u64);
+
+        assert_eq!(total_price(price, 3), Ok(U512::from(30u64)));
+        assert_eq!(total_price(U512::MAX, 2), Err(Error::InsufficientPayment));
+
+        assert_eq!(check_funds(&[U512::from(30u64), U512::from(40u64)], price * 3), Ok(()));
+        assert_eq!(check_funds(&[U512::from(29u64)], price * 3), Err(Error::InsufficientPayment));
+
+        assert_eq!(check_received(U512::from(5u64), U512::from(15u64), price), Ok(()));
+        assert_eq!(
+            check_received(U512::from(5u64), U512::from(14u64), price),
+            Err(Error::InsufficientPayment)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use alloc::vec::Vec;
+use casper_types::account::AccountHash;
+
+/// The lootbox state the core logic reads and writes.
+///
+/// Missing entries read as their default: an empty pool slot or position is `None`,
+/// counters are zero and claim lists are empty.
+pub trait Store {
+    /// Number of unassigned items in the pool of each rarity level.
+    fn item_pool_sizes(&self) -> Vec<u64>;
+    fn set_item_pool_sizes(&mut self, item_pool_sizes: Vec<u64>);
+
+    fn pool_item(&self, rarity: u64, position: u64) -> Option<u64>;
+    fn set_pool_item(&mut self, rarity: u64, position: u64, item_id: u64);
+
+    /// Position of an item in the pool of its rarity level.
+    fn item_position(&self, item_id: u64) -> Option<u64>;
+    fn set_item_position(&mut self, item_id: u64, position: u64);
+
+    /// Items set aside for boxes that are sold but not yet revealed.
+    fn reserved_item_count(&self) -> u64;
+    fn set_reserved_item_count(&mut self, reserved_item_count: u64);
+
+    fn purchase_count(&self, account: AccountHash) -> u64;
+    fn set_purchase_count(&mut self, account: AccountHash, purchase_count: u64);
+
+    fn item_owner(&self, item_id: u64) -> Option<AccountHash>;
+    fn set_item_owner(&mut self, item_id: u64, owner: AccountHash);
+
+    fn is_claimed(&self, item_id: u64) -> bool;
+    fn set_claimed(&mut self, item_id: u64);
+
+    /// Items won by `account` that it has not claimed yet.
+    fn pending_claims(&self, account: AccountHash) -> Vec<u64>;
+    fn set_pending_claims(&mut self, account: AccountHash, item_ids: Vec<u64>);
+
+    fn is_paused(&self) -> bool;
+    fn set_paused(&mut self, paused: bool);
+
+    /// Block times the sale opens at and closes at; `None` leaves that side open.
+    fn sale_window(&self) -> (Option<u64>, Option<u64>);
+    fn set_sale_window(&mut self, sale_start: Option<u64>, sale_end: Option<u64>);
+
+    fn allowlist_only(&self) -> bool;
+    fn set_allowlist_only(&mut self, allowlist_only: bool);
+
+    fn is_allowlisted(&self, account: AccountHash) -> bool;
+    fn set_allowlisted(&mut self, account: AccountHash, allowed: bool);
+}
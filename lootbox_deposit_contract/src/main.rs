@@ -8,36 +8,75 @@ use casper_contract::{
     contract_api::{ account, runtime, system },
     unwrap_or_revert::UnwrapOrRevert,
 };
-use casper_types::{ runtime_args, ContractHash, RuntimeArgs, URef, U512 };
+use casper_types::{ runtime_args, ApiError, ContractHash, Key, RuntimeArgs, URef, U512 };
 
 const LOOTBOX_CONTRACT_HASH: &str = "lootbox_contract_hash";
-// const ENTRY_POINT_GET_PRICE: &str = "get_price";
-const ENTRY_POINT_GET_PURSE: &str = "get_purse";
+const ENTRY_POINT_GET_PRICE: &str = "get_price";
+const ENTRY_POINT_GET_PAYMENT_TOKEN: &str = "get_payment_token";
 const ENTRY_POINT_PURCHASE: &str = "purchase";
-const AMOUNT: &str = "amount";
+const PAYMENT_PURSE: &str = "payment_purse";
+const COMMITMENT: &str = "commitment";
+const QUANTITY: &str = "quantity";
+
+// same code as the lootbox's `InsufficientPayment`
+const ERROR_INSUFFICIENT_PAYMENT: u16 = 8;
 
 #[no_mangle]
 pub extern "C" fn call() {
     let lootbox_contract_hash: ContractHash = runtime::get_named_arg(LOOTBOX_CONTRACT_HASH);
-    let amount: U512 = runtime::get_named_arg(AMOUNT);
+    // sha3-256 of the secret the buyer later passes to `reveal`; sealed boxes are committed
+    // to when they are opened, so sealed sales pass `None`
+    let commitment: Option<[u8; 32]> = runtime::get_named_arg(COMMITMENT);
+    // number of boxes bought at once, all revealed with the same secret
+    let quantity: u64 = runtime::get_named_arg(QUANTITY);
+
+    let mut purchase_args = runtime_args! {
+        QUANTITY => quantity,
+    };
+
+    if let Some(commitment) = commitment {
+        purchase_args.insert(COMMITMENT, commitment).unwrap_or_revert();
+    }
+
+    let payment_token: Option<Key> = runtime::call_contract(
+        lootbox_contract_hash,
+        ENTRY_POINT_GET_PAYMENT_TOKEN,
+        runtime_args! {}
+    );
 
-    // let amount: U512 = runtime::call_contract(
-    //     lootbox_contract_hash,
-    //     ENTRY_POINT_GET_PRICE,
-    //     runtime_args! {}
-    // );
+    // token sales pull the price through the allowance the buyer gave the lootbox beforehand,
+    // so there is no CSPR to move
+    if payment_token.is_some() {
+        runtime::call_contract::<()>(lootbox_contract_hash, ENTRY_POINT_PURCHASE, purchase_args);
+        return;
+    }
 
-    let deposit_purse: URef = runtime::call_contract(
+    let lootbox_price: U512 = runtime::call_contract(
         lootbox_contract_hash,
-        ENTRY_POINT_GET_PURSE,
+        ENTRY_POINT_GET_PRICE,
         runtime_args! {}
     );
+    let amount: U512 = lootbox_price
+        .checked_mul(U512::from(quantity))
+        .unwrap_or_revert_with(ApiError::User(ERROR_INSUFFICIENT_PAYMENT));
+
+    let main_purse: URef = account::get_main_purse();
+    let balance: U512 = system::get_purse_balance(main_purse).unwrap_or_revert();
+
+    if balance < amount {
+        runtime::revert(ApiError::User(ERROR_INSUFFICIENT_PAYMENT));
+    }
+
+    let payment_purse: URef = system::create_purse();
 
     // Transfer from the caller's main purse to the new purse that was just created.
     // Note that transfer is done safely by the host logic.
     system
-        ::transfer_from_purse_to_purse(account::get_main_purse(), deposit_purse, amount, None)
+        ::transfer_from_purse_to_purse(main_purse, payment_purse, amount, None)
         .unwrap_or_revert();
 
-    runtime::call_contract::<()>(lootbox_contract_hash, ENTRY_POINT_PURCHASE, runtime_args! {});
+    // the lootbox contract checks the purse balance against its price, takes the payment
+    // and sends anything above it back to the buyer
+    purchase_args.insert(PAYMENT_PURSE, payment_purse).unwrap_or_revert();
+    runtime::call_contract::<()>(lootbox_contract_hash, ENTRY_POINT_PURCHASE, purchase_args);
 }
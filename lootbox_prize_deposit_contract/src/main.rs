@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+// #[cfg(not(target_arch = "wasm32"))]
+// compile_error!("target arch should be wasm32: compile with '--target wasm32-unknown-unknown'");
+
+extern crate alloc;
+
+use alloc::string::String;
+use casper_contract::{
+    contract_api::{ account, runtime, system },
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{ runtime_args, ApiError, ContractHash, RuntimeArgs, URef, U512 };
+
+const LOOTBOX_CONTRACT_HASH: &str = "lootbox_contract_hash";
+const ENTRY_POINT_ADD_NATIVE_PRIZE: &str = "add_native_prize";
+const SOURCE_PURSE: &str = "source_purse";
+const AMOUNT: &str = "amount";
+const ITEM_NAME: &str = "item_name";
+const RARITY: &str = "rarity";
+
+// same code as the lootbox's `InsufficientBalance`
+const ERROR_INSUFFICIENT_BALANCE: u16 = 28;
+
+// Funds a CSPR prize: the lootbox takes it from a purse handed over with the call, so the prize
+// is moved into a fresh purse first and the caller's main purse is never exposed to the contract.
+#[no_mangle]
+pub extern "C" fn call() {
+    let lootbox_contract_hash: ContractHash = runtime::get_named_arg(LOOTBOX_CONTRACT_HASH);
+    let amount: U512 = runtime::get_named_arg(AMOUNT);
+    let item_name: String = runtime::get_named_arg(ITEM_NAME);
+    let rarity: u64 = runtime::get_named_arg(RARITY);
+
+    let main_purse: URef = account::get_main_purse();
+    let balance: U512 = system::get_purse_balance(main_purse).unwrap_or_revert();
+
+    if balance < amount {
+        runtime::revert(ApiError::User(ERROR_INSUFFICIENT_BALANCE));
+    }
+
+    let source_purse: URef = system::create_purse();
+
+    system
+        ::transfer_from_purse_to_purse(main_purse, source_purse, amount, None)
+        .unwrap_or_revert();
+
+    runtime::call_contract::<()>(
+        lootbox_contract_hash,
+        ENTRY_POINT_ADD_NATIVE_PRIZE,
+        runtime_args! {
+            SOURCE_PURSE => source_purse,
+            AMOUNT => amount,
+            ITEM_NAME => item_name,
+            RARITY => rarity,
+        }
+    );
+}
@@ -0,0 +1,259 @@
+#![no_std]
+#![no_main]
+
+// Minimal stand-in for a CEP-18 token, used by the integration tests.
+// It only implements the entry points the lootbox calls, plus `mint` and `approve`.
+extern crate alloc;
+
+use alloc::{ string::{ String, ToString }, vec, vec::Vec };
+use casper_contract::{
+    contract_api::{ runtime, storage },
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{
+    bytesrepr::ToBytes,
+    contracts::NamedKeys,
+    system::CallStackElement,
+    ApiError,
+    CLType,
+    CLValue,
+    EntryPoint,
+    EntryPointAccess,
+    EntryPointType,
+    EntryPoints,
+    Key,
+    Parameter,
+    URef,
+    U256,
+};
+
+const BALANCES: &str = "balances";
+const ALLOWANCES: &str = "allowances";
+const CONTRACT_HASH: &str = "mock_cep18_contract_hash";
+const PACKAGE_HASH: &str = "mock_cep18_package_hash";
+
+const OWNER: &str = "owner";
+const SPENDER: &str = "spender";
+const RECIPIENT: &str = "recipient";
+const ADDRESS: &str = "address";
+const AMOUNT: &str = "amount";
+
+const ENTRY_POINT_MINT: &str = "mint";
+const ENTRY_POINT_APPROVE: &str = "approve";
+const ENTRY_POINT_ALLOWANCE: &str = "allowance";
+const ENTRY_POINT_BALANCE_OF: &str = "balance_of";
+const ENTRY_POINT_TRANSFER: &str = "transfer";
+const ENTRY_POINT_TRANSFER_FROM: &str = "transfer_from";
+
+const ERROR_INSUFFICIENT_BALANCE: u16 = 1;
+const ERROR_INSUFFICIENT_ALLOWANCE: u16 = 2;
+const ERROR_INVALID_ADDRESS: u16 = 3;
+
+fn get_dictionary(name: &str) -> URef {
+    *runtime::get_key(name).unwrap_or_revert().as_uref().unwrap_or_revert()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push_str(&alloc::format!("{:02x}", byte));
+    }
+    hex
+}
+
+/// Balances are keyed by the hex of the account or package hash.
+fn balance_key(address: Key) -> String {
+    match address {
+        Key::Account(account_hash) => to_hex(&account_hash.value()),
+        Key::Hash(hash) => to_hex(&hash),
+        _ => runtime::revert(ApiError::User(ERROR_INVALID_ADDRESS)),
+    }
+}
+
+/// Dictionary item keys are limited to 64 characters, so an owner and spender pair is hashed.
+fn allowance_key(owner: Key, spender: Key) -> String {
+    let mut bytes: Vec<u8> = owner.to_bytes().unwrap_or_revert();
+    bytes.extend(spender.to_bytes().unwrap_or_revert());
+
+    to_hex(&runtime::blake2b(bytes))
+}
+
+/// Key of whoever called into this contract; like CEP-18, contracts are identified by their
+/// package hash.
+fn caller_key() -> Key {
+    let call_stack = runtime::get_call_stack();
+    let caller = call_stack.iter().rev().nth(1).unwrap_or_revert();
+
+    match caller {
+        CallStackElement::Session { account_hash } => Key::Account(*account_hash),
+        CallStackElement::StoredSession { account_hash, .. } => Key::Account(*account_hash),
+        CallStackElement::StoredContract { contract_package_hash, .. } => {
+            Key::from(*contract_package_hash)
+        }
+    }
+}
+
+fn read_amount(dictionary: &str, item_key: &str) -> U256 {
+    storage
+        ::dictionary_get::<U256>(get_dictionary(dictionary), item_key)
+        .unwrap_or_revert()
+        .unwrap_or_default()
+}
+
+fn write_amount(dictionary: &str, item_key: &str, amount: U256) {
+    storage::dictionary_put(get_dictionary(dictionary), item_key, amount);
+}
+
+fn move_balance(owner: Key, recipient: Key, amount: U256) {
+    let owner_balance = read_amount(BALANCES, &balance_key(owner));
+
+    if owner_balance < amount {
+        runtime::revert(ApiError::User(ERROR_INSUFFICIENT_BALANCE));
+    }
+
+    write_amount(BALANCES, &balance_key(owner), owner_balance - amount);
+    let recipient_balance = read_amount(BALANCES, &balance_key(recipient));
+    write_amount(BALANCES, &balance_key(recipient), recipient_balance + amount);
+}
+
+#[no_mangle]
+pub extern "C" fn mint() {
+    let owner: Key = runtime::get_named_arg(OWNER);
+    let amount: U256 = runtime::get_named_arg(AMOUNT);
+
+    let balance = read_amount(BALANCES, &balance_key(owner));
+    write_amount(BALANCES, &balance_key(owner), balance + amount);
+}
+
+#[no_mangle]
+pub extern "C" fn approve() {
+    let spender: Key = runtime::get_named_arg(SPENDER);
+    let amount: U256 = runtime::get_named_arg(AMOUNT);
+
+    write_amount(ALLOWANCES, &allowance_key(caller_key(), spender), amount);
+}
+
+#[no_mangle]
+pub extern "C" fn allowance() {
+    let owner: Key = runtime::get_named_arg(OWNER);
+    let spender: Key = runtime::get_named_arg(SPENDER);
+
+    let allowance = read_amount(ALLOWANCES, &allowance_key(owner, spender));
+
+    runtime::ret(CLValue::from_t(allowance).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn balance_of() {
+    let address: Key = runtime::get_named_arg(ADDRESS);
+
+    let balance = read_amount(BALANCES, &balance_key(address));
+
+    runtime::ret(CLValue::from_t(balance).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn transfer() {
+    let recipient: Key = runtime::get_named_arg(RECIPIENT);
+    let amount: U256 = runtime::get_named_arg(AMOUNT);
+
+    move_balance(caller_key(), recipient, amount);
+}
+
+#[no_mangle]
+pub extern "C" fn transfer_from() {
+    let owner: Key = runtime::get_named_arg(OWNER);
+    let recipient: Key = runtime::get_named_arg(RECIPIENT);
+    let amount: U256 = runtime::get_named_arg(AMOUNT);
+
+    let spender = caller_key();
+    let allowance = read_amount(ALLOWANCES, &allowance_key(owner, spender));
+
+    if allowance < amount {
+        runtime::revert(ApiError::User(ERROR_INSUFFICIENT_ALLOWANCE));
+    }
+
+    write_amount(ALLOWANCES, &allowance_key(owner, spender), allowance - amount);
+    move_balance(owner, recipient, amount);
+}
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let balances: URef = storage::new_dictionary(BALANCES).unwrap_or_revert();
+    let allowances: URef = storage::new_dictionary(ALLOWANCES).unwrap_or_revert();
+    runtime::remove_key(BALANCES);
+    runtime::remove_key(ALLOWANCES);
+
+    let mut named_keys = NamedKeys::new();
+    named_keys.insert(BALANCES.to_string(), balances.into());
+    named_keys.insert(ALLOWANCES.to_string(), allowances.into());
+
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_MINT,
+            vec![Parameter::new(OWNER, CLType::Key), Parameter::new(AMOUNT, CLType::U256)],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_APPROVE,
+            vec![Parameter::new(SPENDER, CLType::Key), Parameter::new(AMOUNT, CLType::U256)],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_ALLOWANCE,
+            vec![Parameter::new(OWNER, CLType::Key), Parameter::new(SPENDER, CLType::Key)],
+            CLType::U256,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_BALANCE_OF,
+            vec![Parameter::new(ADDRESS, CLType::Key)],
+            CLType::U256,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_TRANSFER,
+            vec![Parameter::new(RECIPIENT, CLType::Key), Parameter::new(AMOUNT, CLType::U256)],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_TRANSFER_FROM,
+            vec![
+                Parameter::new(OWNER, CLType::Key),
+                Parameter::new(RECIPIENT, CLType::Key),
+                Parameter::new(AMOUNT, CLType::U256)
+            ],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+
+    let (contract_hash, _contract_version) = storage::new_contract(
+        entry_points,
+        Some(named_keys),
+        Some(PACKAGE_HASH.to_string()),
+        None
+    );
+
+    runtime::put_key(CONTRACT_HASH, contract_hash.into());
+}
@@ -0,0 +1,221 @@
+#![no_std]
+#![no_main]
+
+// Minimal stand-in for a CEP-78 collection in ordinal mode, used by the integration tests.
+// It only implements the entry points the lootbox calls, plus `mint` and `set_approval_for_all`.
+extern crate alloc;
+
+use alloc::{ string::{ String, ToString }, vec };
+use casper_contract::{
+    contract_api::{ runtime, storage },
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{
+    account::AccountHash,
+    contracts::NamedKeys,
+    system::CallStackElement,
+    ApiError,
+    CLType,
+    CLValue,
+    EntryPoint,
+    EntryPointAccess,
+    EntryPointType,
+    EntryPoints,
+    Key,
+    Parameter,
+    URef,
+};
+
+const TOKEN_OWNERS: &str = "token_owners";
+const OPERATORS: &str = "operators";
+const CONTRACT_HASH: &str = "mock_nft_contract_hash";
+const PACKAGE_HASH: &str = "mock_nft_package_hash";
+
+const TOKEN_ID: &str = "token_id";
+const TOKEN_OWNER: &str = "token_owner";
+const OPERATOR: &str = "operator";
+const APPROVE: &str = "approve";
+const SOURCE_KEY: &str = "source_key";
+const TARGET_KEY: &str = "target_key";
+
+const ENTRY_POINT_MINT: &str = "mint";
+const ENTRY_POINT_SET_APPROVAL_FOR_ALL: &str = "set_approval_for_all";
+const ENTRY_POINT_IS_APPROVED_FOR_ALL: &str = "is_approved_for_all";
+const ENTRY_POINT_TRANSFER: &str = "transfer";
+const ENTRY_POINT_OWNER_OF: &str = "owner_of";
+
+const ERROR_NOT_OWNER: u16 = 1;
+const ERROR_NOT_ACCOUNT: u16 = 2;
+const ERROR_NOT_APPROVED: u16 = 3;
+
+fn get_dictionary(name: &str) -> URef {
+    *runtime::get_key(name).unwrap_or_revert().as_uref().unwrap_or_revert()
+}
+
+fn account_key(owner: Key) -> String {
+    let account: AccountHash = owner
+        .into_account()
+        .unwrap_or_revert_with(ApiError::User(ERROR_NOT_ACCOUNT));
+    account.to_string()
+}
+
+/// Key of whoever called into this contract; like CEP-78's `get_verified_caller`, contracts are
+/// identified by their package hash.
+fn caller_key() -> Key {
+    let call_stack = runtime::get_call_stack();
+    let caller = call_stack.iter().rev().nth(1).unwrap_or_revert();
+
+    match caller {
+        CallStackElement::Session { account_hash } => Key::Account(*account_hash),
+        CallStackElement::StoredSession { account_hash, .. } => Key::Account(*account_hash),
+        CallStackElement::StoredContract { contract_package_hash, .. } => {
+            Key::from(*contract_package_hash)
+        }
+    }
+}
+
+fn is_operator(token_owner: Key, operator: Key) -> bool {
+    let approved: Option<Key> = storage
+        ::dictionary_get::<Option<Key>>(get_dictionary(OPERATORS), &account_key(token_owner))
+        .unwrap_or_revert()
+        .flatten();
+
+    approved == Some(operator)
+}
+
+fn read_owner(token_id: u64) -> Option<Key> {
+    storage
+        ::dictionary_get::<Key>(get_dictionary(TOKEN_OWNERS), &token_id.to_string())
+        .unwrap_or_revert()
+}
+
+#[no_mangle]
+pub extern "C" fn mint() {
+    let token_owner: Key = runtime::get_named_arg(TOKEN_OWNER);
+    let token_id: u64 = runtime::get_named_arg(TOKEN_ID);
+
+    storage::dictionary_put(get_dictionary(TOKEN_OWNERS), &token_id.to_string(), token_owner);
+}
+
+#[no_mangle]
+pub extern "C" fn set_approval_for_all() {
+    let operator: Key = runtime::get_named_arg(OPERATOR);
+    let approve: bool = runtime::get_named_arg(APPROVE);
+
+    let owner = account_key(Key::Account(runtime::get_caller()));
+    let operator: Option<Key> = if approve { Some(operator) } else { None };
+
+    storage::dictionary_put(get_dictionary(OPERATORS), &owner, operator);
+}
+
+#[no_mangle]
+pub extern "C" fn is_approved_for_all() {
+    let token_owner: Key = runtime::get_named_arg(TOKEN_OWNER);
+    let operator: Key = runtime::get_named_arg(OPERATOR);
+
+    runtime::ret(CLValue::from_t(is_operator(token_owner, operator)).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn transfer() {
+    let token_id: u64 = runtime::get_named_arg(TOKEN_ID);
+    let source_key: Key = runtime::get_named_arg(SOURCE_KEY);
+    let target_key: Key = runtime::get_named_arg(TARGET_KEY);
+
+    if read_owner(token_id) != Some(source_key) {
+        runtime::revert(ApiError::User(ERROR_NOT_OWNER));
+    }
+
+    // like CEP-78, only the owner itself or an operator it approved may move the token
+    let caller = caller_key();
+    let is_approved = match source_key {
+        Key::Account(_) => caller == source_key || is_operator(source_key, caller),
+        _ => caller == source_key,
+    };
+
+    if !is_approved {
+        runtime::revert(ApiError::User(ERROR_NOT_APPROVED));
+    }
+
+    storage::dictionary_put(get_dictionary(TOKEN_OWNERS), &token_id.to_string(), target_key);
+}
+
+#[no_mangle]
+pub extern "C" fn owner_of() {
+    let token_id: u64 = runtime::get_named_arg(TOKEN_ID);
+    let owner: Key = read_owner(token_id).unwrap_or_revert_with(ApiError::User(ERROR_NOT_OWNER));
+
+    runtime::ret(CLValue::from_t(owner).unwrap_or_revert());
+}
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let token_owners: URef = storage::new_dictionary(TOKEN_OWNERS).unwrap_or_revert();
+    let operators: URef = storage::new_dictionary(OPERATORS).unwrap_or_revert();
+    runtime::remove_key(TOKEN_OWNERS);
+    runtime::remove_key(OPERATORS);
+
+    let mut named_keys = NamedKeys::new();
+    named_keys.insert(TOKEN_OWNERS.to_string(), token_owners.into());
+    named_keys.insert(OPERATORS.to_string(), operators.into());
+
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_MINT,
+            vec![Parameter::new(TOKEN_OWNER, CLType::Key), Parameter::new(TOKEN_ID, CLType::U64)],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_SET_APPROVAL_FOR_ALL,
+            vec![Parameter::new(OPERATOR, CLType::Key), Parameter::new(APPROVE, CLType::Bool)],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_IS_APPROVED_FOR_ALL,
+            vec![Parameter::new(TOKEN_OWNER, CLType::Key), Parameter::new(OPERATOR, CLType::Key)],
+            CLType::Bool,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_TRANSFER,
+            vec![
+                Parameter::new(TOKEN_ID, CLType::U64),
+                Parameter::new(SOURCE_KEY, CLType::Key),
+                Parameter::new(TARGET_KEY, CLType::Key)
+            ],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_OWNER_OF,
+            vec![Parameter::new(TOKEN_ID, CLType::U64)],
+            CLType::Key,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+
+    let (contract_hash, _contract_version) = storage::new_contract(
+        entry_points,
+        Some(named_keys),
+        Some(PACKAGE_HASH.to_string()),
+        None
+    );
+
+    runtime::put_key(CONTRACT_HASH, contract_hash.into());
+}
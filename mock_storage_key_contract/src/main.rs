@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+// Stand-in for the `storage_key` registry the lootbox registers itself with on install.
+extern crate alloc;
+
+use alloc::{ string::{ String, ToString }, vec, vec::Vec };
+use casper_contract::{
+    contract_api::{ runtime, storage },
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{
+    contracts::NamedKeys,
+    CLType,
+    EntryPoint,
+    EntryPointAccess,
+    EntryPointType,
+    EntryPoints,
+    Parameter,
+    URef,
+};
+
+const DATA: &str = "data";
+const CONTRACT_HASH: &str = "mock_storage_key_contract_hash";
+const PACKAGE_HASH: &str = "mock_storage_key_package_hash";
+
+const ENTRY_POINT_INSERT: &str = "insert";
+
+#[no_mangle]
+pub extern "C" fn insert() {
+    let data: String = runtime::get_named_arg(DATA);
+
+    let data_uref: URef = *runtime::get_key(DATA).unwrap_or_revert().as_uref().unwrap_or_revert();
+    let mut entries: Vec<String> = storage::read(data_uref).unwrap_or_revert().unwrap_or_revert();
+    entries.push(data);
+
+    storage::write(data_uref, entries);
+}
+
+#[no_mangle]
+pub extern "C" fn call() {
+    let entries: Vec<String> = Vec::new();
+
+    let mut named_keys = NamedKeys::new();
+    named_keys.insert(DATA.to_string(), storage::new_uref(entries).into());
+
+    let mut entry_points = EntryPoints::new();
+    entry_points.add_entry_point(
+        EntryPoint::new(
+            ENTRY_POINT_INSERT,
+            vec![Parameter::new(DATA, CLType::String)],
+            CLType::Unit,
+            EntryPointAccess::Public,
+            EntryPointType::Contract
+        )
+    );
+
+    let (contract_hash, _contract_version) = storage::new_contract(
+        entry_points,
+        Some(named_keys),
+        Some(PACKAGE_HASH.to_string()),
+        None
+    );
+
+    runtime::put_key(CONTRACT_HASH, contract_hash.into());
+}
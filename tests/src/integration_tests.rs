@@ -0,0 +1,1121 @@
+#[cfg(test)]
+mod tests {
+    use casper_engine_test_support::{
+        ExecuteRequestBuilder,
+        InMemoryWasmTestBuilder,
+        DEFAULT_ACCOUNT_ADDR,
+        DEFAULT_PAYMENT,
+        DEFAULT_RUN_GENESIS_REQUEST,
+    };
+    use casper_execution_engine::core::{
+        engine_state::Error as EngineStateError,
+        execution::Error as ExecError,
+    };
+    use casper_types::{
+        account::AccountHash,
+        bytesrepr::FromBytes,
+        runtime_args,
+        system::mint,
+        ApiError,
+        CLTyped,
+        ContractHash,
+        ContractPackageHash,
+        Key,
+        RuntimeArgs,
+        URef,
+        U256,
+        U512,
+    };
+    use tiny_keccak::{ Hasher, Sha3 };
+
+    const LOOTBOX_WASM: &str = "lootbox.wasm";
+    const DEPOSIT_WASM: &str = "lootbox_deposit_contract.wasm";
+    const PRIZE_DEPOSIT_WASM: &str = "lootbox_prize_deposit_contract.wasm";
+    const MOCK_CEP18_WASM: &str = "mock_cep18_contract.wasm";
+    const MOCK_NFT_WASM: &str = "mock_nft_contract.wasm";
+    const MOCK_STORAGE_KEY_WASM: &str = "mock_storage_key_contract.wasm";
+
+    const LOOTBOX_CONTRACT_HASH_PREFIX: &str = "lootbox_contract_hash_";
+    const LOOTBOX_PACKAGE_HASH_PREFIX: &str = "lootbox_package_hash_";
+    const MOCK_NFT_CONTRACT_HASH: &str = "mock_nft_contract_hash";
+    const MOCK_CEP18_CONTRACT_HASH: &str = "mock_cep18_contract_hash";
+    const MOCK_STORAGE_KEY_CONTRACT_HASH: &str = "mock_storage_key_contract_hash";
+
+    const SECOND_ACCOUNT: AccountHash = AccountHash::new([7u8; 32]);
+    const SECOND_ACCOUNT_FUNDS: u64 = 100_000_000_000_000;
+    // only ever funded with less than it tries to spend
+    const THIRD_ACCOUNT: AccountHash = AccountHash::new([9u8; 32]);
+
+    const LOOTBOX_PRICE: u64 = 10_000_000_000;
+    const TOKEN_COUNT: u64 = 3;
+    const PRIZE_AMOUNT: u64 = 5_000_000_000;
+    const INSTALL_TIME: u64 = 1_000;
+    const PURCHASE_TIME: u64 = 2_000;
+    const REVEAL_DELAY: u64 = 100;
+    const REVEAL_TIME: u64 = PURCHASE_TIME + REVEAL_DELAY;
+    const REVEAL_WINDOW: u64 = 60_000;
+    const SECRET: &str = "lootbox secret";
+
+    // lootbox error codes, see lootbox/src/error.rs
+    const ERROR_ADMIN: u16 = 1;
+    const ERROR_NOT_APPROVED: u16 = 2;
+    const ERROR_LOOTBOX_LIMIT: u16 = 3;
+    const ERROR_INSUFFICIENT_PAYMENT: u16 = 8;
+    const ERROR_REVEAL_TOO_EARLY: u16 = 13;
+    const ERROR_NOT_ITEM_OWNER: u16 = 17;
+    const ERROR_INSUFFICIENT_BALANCE: u16 = 28;
+    const ERROR_ITEMS_RESERVED: u16 = 43;
+
+    struct TestContext {
+        builder: InMemoryWasmTestBuilder,
+        lootbox: ContractHash,
+        // CEP-78 sees the lootbox as a caller by its package hash
+        lootbox_package: ContractPackageHash,
+        nft: ContractHash,
+    }
+
+    fn sha3_256(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha3::v256();
+        hasher.update(data);
+        let mut output = [0u8; 32];
+        hasher.finalize(&mut output);
+        output
+    }
+
+    fn to_dictionary_key(account: AccountHash) -> String {
+        to_hex(&account.value())
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn account_contract_hash(builder: &InMemoryWasmTestBuilder, name: &str) -> ContractHash {
+        builder
+            .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
+            .named_keys()
+            .iter()
+            .find(|(key_name, _)| key_name.starts_with(name))
+            .and_then(|(_, key)| key.into_hash())
+            .map(ContractHash::new)
+            .expect("contract hash should be stored under the installing account")
+    }
+
+    fn account_package_hash(builder: &InMemoryWasmTestBuilder, name: &str) -> ContractPackageHash {
+        builder
+            .get_expected_account(*DEFAULT_ACCOUNT_ADDR)
+            .named_keys()
+            .iter()
+            .find(|(key_name, _)| key_name.starts_with(name))
+            .and_then(|(_, key)| key.into_hash())
+            .map(ContractPackageHash::new)
+            .expect("package hash should be stored under the installing account")
+    }
+
+    fn named_key_value<T: CLTyped + FromBytes>(
+        builder: &InMemoryWasmTestBuilder,
+        contract: ContractHash,
+        name: &str
+    ) -> T {
+        builder
+            .query(None, Key::from(contract), &[name.to_string()])
+            .expect("named key should exist")
+            .as_cl_value()
+            .expect("named key should hold a CLValue")
+            .clone()
+            .into_t()
+            .expect("named key should have the expected type")
+    }
+
+    fn contract_uref(
+        builder: &InMemoryWasmTestBuilder,
+        contract: ContractHash,
+        name: &str
+    ) -> URef {
+        *builder
+            .get_contract(contract)
+            .expect("contract should exist")
+            .named_keys()
+            .get(name)
+            .expect("named key should exist")
+            .as_uref()
+            .expect("named key should be a URef")
+    }
+
+    fn dictionary_value<T: CLTyped + FromBytes>(
+        builder: &InMemoryWasmTestBuilder,
+        contract: ContractHash,
+        dictionary: &str,
+        key: &str
+    ) -> Option<T> {
+        let seed_uref: URef = contract_uref(builder, contract, dictionary);
+
+        builder
+            .query_dictionary_item(None, seed_uref, key)
+            .ok()
+            .map(|value| {
+                value.as_cl_value().expect("item should hold a CLValue").clone().into_t().unwrap()
+            })
+    }
+
+    fn main_purse_balance(builder: &InMemoryWasmTestBuilder, account: AccountHash) -> U512 {
+        let main_purse: URef = builder.get_expected_account(account).main_purse();
+        builder.get_purse_balance(main_purse)
+    }
+
+    fn call_lootbox(
+        context: &mut TestContext,
+        sender: AccountHash,
+        entry_point: &str,
+        args: RuntimeArgs,
+        block_time: u64
+    ) {
+        let request = ExecuteRequestBuilder::contract_call_by_hash(
+            sender,
+            context.lootbox,
+            entry_point,
+            args
+        )
+            .with_block_time(block_time)
+            .build();
+        context.builder.exec(request).commit();
+    }
+
+    fn purchase(context: &mut TestContext, buyer: AccountHash, quantity: u64) {
+        purchase_with(context, buyer, quantity, Some(sha3_256(SECRET.as_bytes())));
+    }
+
+    fn purchase_with(
+        context: &mut TestContext,
+        buyer: AccountHash,
+        quantity: u64,
+        commitment: Option<[u8; 32]>
+    ) {
+        let request = ExecuteRequestBuilder::standard(
+            buyer,
+            DEPOSIT_WASM,
+            runtime_args! {
+                "lootbox_contract_hash" => context.lootbox,
+                "commitment" => commitment,
+                "quantity" => quantity,
+            }
+        )
+            .with_block_time(PURCHASE_TIME)
+            .build();
+        context.builder.exec(request).commit();
+    }
+
+    fn add_item(context: &mut TestContext, token_id: u64, rarity: u64) {
+        call_lootbox(
+            context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "add_item",
+            runtime_args! {
+                "collection" => Key::from(context.nft),
+                "item_name" => format!("item {}", token_id),
+                "token_id" => token_id,
+                "rarity" => rarity,
+            },
+            INSTALL_TIME
+        );
+    }
+
+    fn assert_user_error(builder: &InMemoryWasmTestBuilder, expected: u16) {
+        let error = builder.get_error().expect("deploy should have failed");
+        assert!(
+            matches!(
+                error,
+                EngineStateError::Exec(ExecError::Revert(ApiError::User(code))) if code == expected
+            ),
+            "expected user error {}, got {:?}",
+            expected,
+            error
+        );
+    }
+
+    /// Installs the mocks and the lootbox, funds a second account and mints the test tokens
+    /// to the default account. The lootbox is not approved as an operator yet.
+    fn setup() -> TestContext {
+        setup_with(false)
+    }
+
+    /// Same as `setup`, with the box mode chosen by the caller.
+    fn setup_with(sealed_boxes: bool) -> TestContext {
+        let mut builder = InMemoryWasmTestBuilder::default();
+        builder.run_genesis(&*DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+        fund_account(&mut builder, SECOND_ACCOUNT, U512::from(SECOND_ACCOUNT_FUNDS));
+
+        for wasm in [MOCK_STORAGE_KEY_WASM, MOCK_NFT_WASM] {
+            let request = ExecuteRequestBuilder::standard(
+                *DEFAULT_ACCOUNT_ADDR,
+                wasm,
+                runtime_args! {}
+            ).build();
+            builder.exec(request).expect_success().commit();
+        }
+
+        let storage_key = account_contract_hash(&builder, MOCK_STORAGE_KEY_CONTRACT_HASH);
+        let nft = account_contract_hash(&builder, MOCK_NFT_CONTRACT_HASH);
+
+        let install_request = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            LOOTBOX_WASM,
+            runtime_args! {
+                "name" => "test lootbox".to_string(),
+                "description" => "integration test lootbox".to_string(),
+                "asset" => "asset".to_string(),
+                "nft_collection" => Key::from(nft),
+                "lootbox_price" => U512::from(LOOTBOX_PRICE),
+                "items_per_lootbox" => 1u64,
+                "max_lootboxes" => TOKEN_COUNT,
+                "max_items" => TOKEN_COUNT,
+                "storage_key" => storage_key,
+                "reveal_window" => REVEAL_WINDOW,
+                "reveal_delay" => REVEAL_DELAY,
+                "rarity_weights" => vec![1u64, 1u64, 1u64],
+                "max_purchases_per_account" => 0u64,
+                "allowlist_only" => false,
+                "sale_start" => <Option<u64>>::None,
+                "sale_end" => <Option<u64>>::None,
+                "payees" => Vec::<AccountHash>::new(),
+                "shares" => Vec::<u64>::new(),
+                "payment_token" => <Option<Key>>::None,
+                "identifier_mode" => 0u8,
+                "sealed_boxes" => sealed_boxes,
+            }
+        )
+            .with_block_time(INSTALL_TIME)
+            .build();
+        builder.exec(install_request).expect_success().commit();
+
+        let lootbox = account_contract_hash(&builder, LOOTBOX_CONTRACT_HASH_PREFIX);
+        let lootbox_package = account_package_hash(&builder, LOOTBOX_PACKAGE_HASH_PREFIX);
+
+        for token_id in 0..TOKEN_COUNT {
+            let mint_request = ExecuteRequestBuilder::contract_call_by_hash(
+                *DEFAULT_ACCOUNT_ADDR,
+                nft,
+                "mint",
+                runtime_args! {
+                    "token_owner" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+                    "token_id" => token_id,
+                }
+            ).build();
+            builder.exec(mint_request).expect_success().commit();
+        }
+
+        TestContext { builder, lootbox, lootbox_package, nft }
+    }
+
+    fn fund_account(builder: &mut InMemoryWasmTestBuilder, account: AccountHash, amount: U512) {
+        let fund_request = ExecuteRequestBuilder::transfer(
+            *DEFAULT_ACCOUNT_ADDR,
+            runtime_args! {
+                mint::ARG_TARGET => account,
+                mint::ARG_AMOUNT => amount,
+                mint::ARG_ID => <Option<u64>>::None,
+            }
+        ).build();
+        builder.exec(fund_request).expect_success().commit();
+    }
+
+    fn approve_lootbox(context: &mut TestContext) {
+        let operator = Key::from(context.lootbox_package);
+        approve_operator(context, operator);
+    }
+
+    fn approve_operator(context: &mut TestContext, operator: Key) {
+        let request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            context.nft,
+            "set_approval_for_all",
+            runtime_args! {
+                "operator" => operator,
+                "approve" => true,
+            }
+        ).build();
+        context.builder.exec(request).expect_success().commit();
+    }
+
+    #[test]
+    fn should_install_and_register_with_storage_key() {
+        let context = setup();
+
+        let storage_key = account_contract_hash(&context.builder, MOCK_STORAGE_KEY_CONTRACT_HASH);
+        let entries: Vec<String> = named_key_value(&context.builder, storage_key, "data");
+
+        assert_eq!(entries, vec![context.lootbox.to_string()]);
+    }
+
+    #[test]
+    fn should_add_purchase_reveal_claim_and_withdraw() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        let token_owner: Option<Key> = dictionary_value(
+            &context.builder,
+            context.nft,
+            "token_owners",
+            "0"
+        );
+        assert_eq!(token_owner, Some(Key::from(context.lootbox_package)));
+
+        let stock: u64 = named_key_value::<Vec<u64>>(
+            &context.builder,
+            context.lootbox,
+            "item_pool_sizes"
+        )
+            .iter()
+            .sum();
+        assert_eq!(stock, 1);
+
+        purchase(&mut context, SECOND_ACCOUNT, 1);
+        context.builder.expect_success();
+
+        let contract_purse: URef = contract_uref(&context.builder, context.lootbox, "purse");
+        assert_eq!(context.builder.get_purse_balance(contract_purse), U512::from(LOOTBOX_PRICE));
+
+        let escrowed: U512 = named_key_value(&context.builder, context.lootbox, "escrowed_amount");
+        assert_eq!(escrowed, U512::from(LOOTBOX_PRICE));
+
+        let paid: Option<U512> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "account_payments",
+            &to_dictionary_key(SECOND_ACCOUNT)
+        );
+        assert_eq!(paid, Some(U512::from(LOOTBOX_PRICE)));
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "reveal",
+            runtime_args! { "secret" => SECRET.to_string() },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        let item_owner: Option<AccountHash> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "item_owners",
+            "0"
+        );
+        assert_eq!(item_owner, Some(SECOND_ACCOUNT));
+
+        let pending_claims: Option<Vec<u64>> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "pending_claims",
+            &to_dictionary_key(SECOND_ACCOUNT)
+        );
+        assert_eq!(pending_claims, Some(vec![0u64]));
+
+        let total_revenue: U512 = named_key_value(
+            &context.builder,
+            context.lootbox,
+            "total_revenue"
+        );
+        assert_eq!(total_revenue, U512::from(LOOTBOX_PRICE));
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "claim",
+            runtime_args! { "item_index" => 0u64 },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        let token_owner: Option<Key> = dictionary_value(
+            &context.builder,
+            context.nft,
+            "token_owners",
+            "0"
+        );
+        assert_eq!(token_owner, Some(Key::Account(SECOND_ACCOUNT)));
+
+        let claimed: Option<bool> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "claimed_items",
+            "0"
+        );
+        assert_eq!(claimed, Some(true));
+
+        let balance_before = main_purse_balance(&context.builder, SECOND_ACCOUNT);
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "withdraw",
+            runtime_args! {
+                "amount" => U512::from(LOOTBOX_PRICE),
+                "target" => Key::Account(SECOND_ACCOUNT),
+            },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        assert_eq!(
+            main_purse_balance(&context.builder, SECOND_ACCOUNT),
+            balance_before + U512::from(LOOTBOX_PRICE)
+        );
+        assert_eq!(context.builder.get_purse_balance(contract_purse), U512::zero());
+    }
+
+    /// Buys one box as `buyer` and reveals it.
+    fn purchase_and_reveal(context: &mut TestContext, buyer: AccountHash) {
+        purchase(context, buyer, 1);
+        context.builder.expect_success();
+
+        call_lootbox(
+            context,
+            buyer,
+            "reveal",
+            runtime_args! { "secret" => SECRET.to_string() },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+    }
+
+    #[test]
+    fn should_add_and_claim_native_prize() {
+        let mut context = setup();
+
+        // the session funds a purse of its own, so the admin's main purse is never handed over
+        let request = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            PRIZE_DEPOSIT_WASM,
+            runtime_args! {
+                "lootbox_contract_hash" => context.lootbox,
+                "amount" => U512::from(PRIZE_AMOUNT),
+                "item_name" => "cspr prize".to_string(),
+                "rarity" => 0u64,
+            }
+        )
+            .with_block_time(INSTALL_TIME)
+            .build();
+        context.builder.exec(request).expect_success().commit();
+
+        let prize_purse: URef = contract_uref(&context.builder, context.lootbox, "prize_purse");
+        assert_eq!(context.builder.get_purse_balance(prize_purse), U512::from(PRIZE_AMOUNT));
+
+        purchase_and_reveal(&mut context, SECOND_ACCOUNT);
+
+        let balance_before = main_purse_balance(&context.builder, SECOND_ACCOUNT);
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "claim",
+            runtime_args! { "item_index" => 0u64 },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        // the claim's own gas comes out of the same purse
+        assert!(
+            main_purse_balance(&context.builder, SECOND_ACCOUNT) + *DEFAULT_PAYMENT >=
+                balance_before + U512::from(PRIZE_AMOUNT)
+        );
+        assert_eq!(context.builder.get_purse_balance(prize_purse), U512::zero());
+    }
+
+    #[test]
+    fn should_add_and_claim_token_prize() {
+        let mut context = setup();
+
+        let request = ExecuteRequestBuilder::standard(
+            *DEFAULT_ACCOUNT_ADDR,
+            MOCK_CEP18_WASM,
+            runtime_args! {}
+        ).build();
+        context.builder.exec(request).expect_success().commit();
+        let token = account_contract_hash(&context.builder, MOCK_CEP18_CONTRACT_HASH);
+
+        let mint_request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            token,
+            "mint",
+            runtime_args! {
+                "owner" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+                "amount" => U256::from(PRIZE_AMOUNT),
+            }
+        ).build();
+        context.builder.exec(mint_request).expect_success().commit();
+
+        // like CEP-18, the token sees the lootbox as a spender by its package hash
+        let approve_request = ExecuteRequestBuilder::contract_call_by_hash(
+            *DEFAULT_ACCOUNT_ADDR,
+            token,
+            "approve",
+            runtime_args! {
+                "spender" => Key::from(context.lootbox_package),
+                "amount" => U256::from(PRIZE_AMOUNT),
+            }
+        ).build();
+        context.builder.exec(approve_request).expect_success().commit();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "add_token_prize",
+            runtime_args! {
+                "token" => Key::from(token),
+                "amount" => U256::from(PRIZE_AMOUNT),
+                "item_name" => "token prize".to_string(),
+                "rarity" => 0u64,
+            },
+            INSTALL_TIME
+        );
+        context.builder.expect_success();
+
+        let lootbox_balance: Option<U256> = dictionary_value(
+            &context.builder,
+            token,
+            "balances",
+            &to_hex(&context.lootbox_package.value())
+        );
+        assert_eq!(lootbox_balance, Some(U256::from(PRIZE_AMOUNT)));
+
+        purchase_and_reveal(&mut context, SECOND_ACCOUNT);
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "claim",
+            runtime_args! { "item_index" => 0u64 },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        let buyer_balance: Option<U256> = dictionary_value(
+            &context.builder,
+            token,
+            "balances",
+            &to_dictionary_key(SECOND_ACCOUNT)
+        );
+        assert_eq!(buyer_balance, Some(U256::from(PRIZE_AMOUNT)));
+
+        let lootbox_balance: Option<U256> = dictionary_value(
+            &context.builder,
+            token,
+            "balances",
+            &to_hex(&context.lootbox_package.value())
+        );
+        assert_eq!(lootbox_balance, Some(U256::zero()));
+    }
+
+    #[test]
+    fn should_move_item_between_rarity_pools() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "set_rarity",
+            runtime_args! { "item_index" => 0u64, "rarity" => 2u64 },
+            INSTALL_TIME
+        );
+        context.builder.expect_success();
+
+        let item_pool_sizes: Vec<u64> = named_key_value(
+            &context.builder,
+            context.lootbox,
+            "item_pool_sizes"
+        );
+        assert_eq!(item_pool_sizes, vec![0u64, 0u64, 1u64]);
+    }
+
+    #[test]
+    fn should_reject_set_rarity_without_role() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "set_rarity",
+            runtime_args! { "item_index" => 0u64, "rarity" => 2u64 },
+            INSTALL_TIME
+        );
+
+        assert_user_error(&context.builder, ERROR_ADMIN);
+    }
+
+    #[test]
+    fn should_reject_add_item_without_approval() {
+        let mut context = setup();
+
+        add_item(&mut context, 0, 0);
+
+        assert_user_error(&context.builder, ERROR_NOT_APPROVED);
+    }
+
+    #[test]
+    fn should_reject_add_item_approved_by_contract_hash() {
+        let mut context = setup();
+        // CEP-78 checks operators against the caller's package hash, not its contract hash
+        let operator = Key::from(context.lootbox);
+        approve_operator(&mut context, operator);
+
+        add_item(&mut context, 0, 0);
+
+        assert_user_error(&context.builder, ERROR_NOT_APPROVED);
+    }
+
+    #[test]
+    fn should_reject_purchase_the_buyer_cannot_afford() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        // the session payment leaves half a box worth in the main purse
+        let funds = *DEFAULT_PAYMENT + U512::from(LOOTBOX_PRICE / 2);
+        fund_account(&mut context.builder, THIRD_ACCOUNT, funds);
+
+        purchase(&mut context, THIRD_ACCOUNT, 1);
+
+        assert_user_error(&context.builder, ERROR_INSUFFICIENT_PAYMENT);
+    }
+
+    #[test]
+    fn should_purchase_and_reveal_several_boxes_at_once() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        for token_id in 0..TOKEN_COUNT {
+            add_item(&mut context, token_id, token_id);
+            context.builder.expect_success();
+        }
+
+        purchase(&mut context, SECOND_ACCOUNT, TOKEN_COUNT);
+        context.builder.expect_success();
+
+        let lootbox_count: u64 = named_key_value(
+            &context.builder,
+            context.lootbox,
+            "lootbox_count"
+        );
+        assert_eq!(lootbox_count, TOKEN_COUNT);
+
+        let escrowed: U512 = named_key_value(&context.builder, context.lootbox, "escrowed_amount");
+        assert_eq!(escrowed, U512::from(LOOTBOX_PRICE * TOKEN_COUNT));
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "reveal",
+            runtime_args! { "secret" => SECRET.to_string() },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        let mut pending_claims: Vec<u64> = dictionary_value::<Vec<u64>>(
+            &context.builder,
+            context.lootbox,
+            "pending_claims",
+            &to_dictionary_key(SECOND_ACCOUNT)
+        ).unwrap();
+        pending_claims.sort_unstable();
+        assert_eq!(pending_claims, (0..TOKEN_COUNT).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn should_reject_purchase_above_lootbox_limit() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase(&mut context, SECOND_ACCOUNT, TOKEN_COUNT + 1);
+
+        assert_user_error(&context.builder, ERROR_LOOTBOX_LIMIT);
+    }
+
+    #[test]
+    fn should_reject_multi_purchase_the_buyer_cannot_afford() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+        add_item(&mut context, 1, 0);
+        context.builder.expect_success();
+
+        // enough for one box, but not for two
+        let funds = *DEFAULT_PAYMENT + U512::from(LOOTBOX_PRICE * 3 / 2);
+        fund_account(&mut context.builder, THIRD_ACCOUNT, funds);
+
+        purchase(&mut context, THIRD_ACCOUNT, 2);
+
+        assert_user_error(&context.builder, ERROR_INSUFFICIENT_PAYMENT);
+    }
+
+    #[test]
+    fn should_purchase_sealed_box_without_commitment() {
+        let mut context = setup_with(true);
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase_with(&mut context, SECOND_ACCOUNT, 1, None);
+        context.builder.expect_success();
+
+        let box_owner: Option<AccountHash> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "box_owners",
+            "0"
+        );
+        assert_eq!(box_owner, Some(SECOND_ACCOUNT));
+    }
+
+    #[test]
+    fn should_draw_the_same_items_whenever_the_buyer_reveals() {
+        let won_items: Vec<Option<Vec<u64>>> = [REVEAL_TIME, REVEAL_TIME + 10_000]
+            .iter()
+            .map(|reveal_time| {
+                let mut context = setup();
+                approve_lootbox(&mut context);
+
+                for token_id in 0..TOKEN_COUNT {
+                    add_item(&mut context, token_id, token_id);
+                    context.builder.expect_success();
+                }
+
+                purchase(&mut context, SECOND_ACCOUNT, 1);
+                context.builder.expect_success();
+
+                call_lootbox(
+                    &mut context,
+                    SECOND_ACCOUNT,
+                    "reveal",
+                    runtime_args! { "secret" => SECRET.to_string() },
+                    *reveal_time
+                );
+                context.builder.expect_success();
+
+                dictionary_value(
+                    &context.builder,
+                    context.lootbox,
+                    "pending_claims",
+                    &to_dictionary_key(SECOND_ACCOUNT)
+                )
+            })
+            .collect();
+
+        assert!(won_items[0].is_some());
+        assert_eq!(won_items[0], won_items[1]);
+    }
+
+    #[test]
+    fn should_reject_reveal_before_the_delay() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase(&mut context, SECOND_ACCOUNT, 1);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "reveal",
+            runtime_args! { "secret" => SECRET.to_string() },
+            REVEAL_TIME - 1
+        );
+
+        assert_user_error(&context.builder, ERROR_REVEAL_TOO_EARLY);
+    }
+
+    #[test]
+    fn should_forfeit_payment_of_an_expired_commitment() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase(&mut context, SECOND_ACCOUNT, 1);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "cancel_purchase",
+            runtime_args! {},
+            PURCHASE_TIME + REVEAL_WINDOW + 1
+        );
+        context.builder.expect_success();
+
+        let contract_purse: URef = contract_uref(&context.builder, context.lootbox, "purse");
+        assert_eq!(context.builder.get_purse_balance(contract_purse), U512::from(LOOTBOX_PRICE));
+
+        let escrowed: U512 = named_key_value(&context.builder, context.lootbox, "escrowed_amount");
+        assert_eq!(escrowed, U512::zero());
+
+        let total_revenue: U512 = named_key_value(
+            &context.builder,
+            context.lootbox,
+            "total_revenue"
+        );
+        assert_eq!(total_revenue, U512::from(LOOTBOX_PRICE));
+
+        let reserved: u64 = named_key_value(
+            &context.builder,
+            context.lootbox,
+            "reserved_item_count"
+        );
+        assert_eq!(reserved, 0);
+    }
+
+    #[test]
+    fn should_burn_sealed_box_of_an_expired_opening() {
+        let mut context = setup_with(true);
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase_with(&mut context, SECOND_ACCOUNT, 1, None);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "open_box",
+            runtime_args! {
+                "box_id" => 0u64,
+                "commitment" => sha3_256(SECRET.as_bytes()),
+            },
+            PURCHASE_TIME
+        );
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "cancel_purchase",
+            runtime_args! {},
+            PURCHASE_TIME + REVEAL_WINDOW + 1
+        );
+        context.builder.expect_success();
+
+        let box_owner: Option<Option<AccountHash>> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "box_owners",
+            "0"
+        );
+        assert_eq!(box_owner, Some(None));
+
+        let reserved: u64 = named_key_value(
+            &context.builder,
+            context.lootbox,
+            "reserved_item_count"
+        );
+        assert_eq!(reserved, 0);
+
+        // the box was paid for, so it still counts against the buyer's purchase limit
+        let purchases: Option<u64> = dictionary_value(
+            &context.builder,
+            context.lootbox,
+            "account_purchases",
+            &to_dictionary_key(SECOND_ACCOUNT)
+        );
+        assert_eq!(purchases, Some(1));
+    }
+
+    #[test]
+    fn should_reject_claim_by_another_account() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase(&mut context, SECOND_ACCOUNT, 1);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "reveal",
+            runtime_args! { "secret" => SECRET.to_string() },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "claim",
+            runtime_args! { "item_index" => 0u64 },
+            REVEAL_TIME
+        );
+
+        assert_user_error(&context.builder, ERROR_NOT_ITEM_OWNER);
+    }
+
+    #[test]
+    fn should_keep_earned_revenue_when_payees_change() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        add_item(&mut context, 0, 0);
+        context.builder.expect_success();
+
+        purchase(&mut context, SECOND_ACCOUNT, 1);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            SECOND_ACCOUNT,
+            "reveal",
+            runtime_args! { "secret" => SECRET.to_string() },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "set_payees",
+            runtime_args! {
+                "payees" => vec![THIRD_ACCOUNT],
+                "shares" => vec![5_000u64],
+            },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        // the new payee only shares in revenue earned after the change
+        let settled: U512 = named_key_value(&context.builder, context.lootbox, "settled_revenue");
+        assert_eq!(settled, U512::from(LOOTBOX_PRICE));
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "withdraw",
+            runtime_args! {
+                "amount" => U512::from(LOOTBOX_PRICE),
+                "target" => Key::Account(SECOND_ACCOUNT),
+            },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+    }
+
+    #[test]
+    fn should_reclaim_unsold_items_around_pending_commitments() {
+        let mut context = setup();
+        approve_lootbox(&mut context);
+
+        for token_id in 0..2 {
+            add_item(&mut context, token_id, 0);
+            context.builder.expect_success();
+        }
+
+        // the buyer never reveals, which must not lock the rest of the inventory
+        purchase(&mut context, SECOND_ACCOUNT, 1);
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "set_sale_window",
+            runtime_args! {
+                "sale_start" => <Option<u64>>::None,
+                "sale_end" => Some(REVEAL_TIME),
+            },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "reclaim_unsold",
+            runtime_args! {
+                "recipient" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+                "limit" => 10u64,
+            },
+            REVEAL_TIME
+        );
+        context.builder.expect_success();
+
+        let token_owner: Option<Key> = dictionary_value(
+            &context.builder,
+            context.nft,
+            "token_owners",
+            "1"
+        );
+        assert_eq!(token_owner, Some(Key::Account(*DEFAULT_ACCOUNT_ADDR)));
+
+        let stock: u64 = named_key_value::<Vec<u64>>(
+            &context.builder,
+            context.lootbox,
+            "item_pool_sizes"
+        )
+            .iter()
+            .sum();
+        assert_eq!(stock, 1);
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "reclaim_unsold",
+            runtime_args! {
+                "recipient" => Key::Account(*DEFAULT_ACCOUNT_ADDR),
+                "limit" => 10u64,
+            },
+            REVEAL_TIME
+        );
+
+        assert_user_error(&context.builder, ERROR_ITEMS_RESERVED);
+    }
+
+    #[test]
+    fn should_reject_withdraw_above_revenue() {
+        let mut context = setup();
+
+        call_lootbox(
+            &mut context,
+            *DEFAULT_ACCOUNT_ADDR,
+            "withdraw",
+            runtime_args! {
+                "amount" => U512::one(),
+                "target" => Key::Account(SECOND_ACCOUNT),
+            },
+            INSTALL_TIME
+        );
+
+        assert_user_error(&context.builder, ERROR_INSUFFICIENT_BALANCE);
+    }
+}
+
+fn main() {
+    panic!("Execute \"cargo test\" to test the contract, not \"cargo run\".");
+}